@@ -0,0 +1,235 @@
+//  SPDX-FileCopyrightText: Copyright 2025 James M. Putnam (putnamjm.design@gmail.com)
+//  SPDX-License-Identifier: MIT
+
+//! editor buffers
+use {
+    crate::{
+        highlighter::Mark,
+        paredit::Edit,
+        syntax::{Syntax, Token},
+    },
+    iced::widget::text_editor::{self, Action, Motion},
+    std::{
+        ops::Range,
+        path::PathBuf,
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+    },
+};
+
+// buffers are named by id in messages that outlive the buffer list's shape
+pub type BufferId = usize;
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+pub struct Buffer {
+    pub id: BufferId,
+    pub content: text_editor::Content,
+    pub path: Option<PathBuf>,
+    pub is_dirty: bool,
+    // the environment it evaluates in, by name, and the namespace there
+    pub env: String,
+    pub ns: String,
+    // evaluated again whenever the environment restarts
+    pub reeval: bool,
+    // delimiters marked for the cursor, by line and byte range
+    pub marks: Vec<(usize, Range<usize>, Mark)>,
+    pub unclosed: usize,
+    pub unopened: usize,
+}
+
+impl Buffer {
+    pub fn new(text: &str, path: Option<PathBuf>, env: &str, ns: &str) -> Self {
+        let mut buffer = Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            content: text_editor::Content::with_text(text),
+            path,
+            is_dirty: false,
+            env: env.into(),
+            ns: ns.into(),
+            reeval: false,
+            marks: Vec::new(),
+            unclosed: 0,
+            unopened: 0,
+        };
+
+        buffer.refresh();
+        buffer
+    }
+
+    // recompute delimiter state after an edit or cursor motion
+    pub fn refresh(&mut self) {
+        let text = self.content.text();
+        let delimiters = Syntax::delimiters(&text);
+
+        let (line, column) = self.content.cursor_position();
+        let offset = Syntax::offset(&text, line, column);
+
+        self.unclosed = delimiters
+            .unmatched
+            .iter()
+            .filter(|token| text[token.start..].starts_with(['(', '#']))
+            .count();
+        self.unopened = delimiters.unmatched.len() - self.unclosed;
+
+        self.marks = match Syntax::matching(&delimiters, offset) {
+            Some((delimiter, partner)) => {
+                let mark = |token: Token, mark| {
+                    let (line, column) = Syntax::position(&text, token.start);
+
+                    (line, column..column + (token.end - token.start), mark)
+                };
+
+                match partner {
+                    Some(partner) => {
+                        vec![mark(delimiter, Mark::Matched), mark(partner, Mark::Matched)]
+                    }
+                    None => vec![mark(delimiter, Mark::Unmatched)],
+                }
+            }
+            None => Vec::new(),
+        };
+    }
+
+    pub fn cursor(&self) -> (usize, usize) {
+        self.content.cursor_position()
+    }
+
+    // is the cursor in a string, a comment or a character literal
+    pub fn in_literal(&self) -> bool {
+        let text = self.content.text();
+        let (line, column) = self.cursor();
+
+        Syntax::in_literal(&text, Syntax::offset(&text, line, column))
+    }
+
+    // apply a structural edit as one local edit, the selection runs
+    // forward from the cursor
+    pub fn apply(&mut self, edit: Edit) {
+        let text = match edit.text {
+            Some(text) => {
+                self.patch(&text, edit.cursor);
+                text
+            }
+            None => {
+                let text = self.content.text();
+
+                self.walk(Syntax::position(&text, edit.cursor));
+                text
+            }
+        };
+
+        if let Some(end) = edit.select {
+            (0..text[edit.cursor..end].chars().count())
+                .for_each(|_| self.content.perform(Action::Select(Motion::Right)));
+        }
+
+        self.refresh();
+    }
+
+    pub fn reindented(&self, lines: Range<usize>) -> String {
+        Syntax::reindent(&self.content.text(), lines)
+    }
+
+    // walk the cursor from where it is to a (line, byte column), by lines
+    // and then characters
+    fn walk(&mut self, (line, column): (usize, usize)) {
+        let (at, _) = self.cursor();
+        let chars = match self.content.line(line) {
+            Some(text) => text[..column.min(text.len())].chars().count(),
+            None => 0,
+        };
+
+        let vertical = if line < at { Motion::Up } else { Motion::Down };
+
+        (0..line.abs_diff(at)).for_each(|_| self.content.perform(Action::Move(vertical)));
+        self.content.perform(Action::Move(Motion::Home));
+        (0..chars).for_each(|_| self.content.perform(Action::Move(Motion::Right)));
+    }
+
+    // turn the text into text as one local edit, replacing only what lies
+    // between their common prefix and suffix, and leave the cursor at a
+    // byte offset into the new text
+    pub fn patch(&mut self, text: &str, cursor: usize) {
+        let old = self.content.text();
+
+        let prefix = old
+            .char_indices()
+            .zip(text.chars())
+            .find(|((_, old), new)| old != new)
+            .map_or(old.len().min(text.len()), |((at, _), _)| at);
+
+        let suffix = old[prefix..]
+            .chars()
+            .rev()
+            .zip(text[prefix..].chars().rev())
+            .take_while(|(old, new)| old == new)
+            .map(|(old, _)| old.len_utf8())
+            .sum::<usize>();
+
+        let (removed, inserted) = (
+            &old[prefix..old.len() - suffix],
+            &text[prefix..text.len() - suffix],
+        );
+
+        if !removed.is_empty() || !inserted.is_empty() {
+            self.walk(Syntax::position(&old, prefix));
+            (0..removed.chars().count())
+                .for_each(|_| self.content.perform(Action::Select(Motion::Right)));
+
+            self.content.perform(Action::Edit(match inserted {
+                "" => text_editor::Edit::Delete,
+                _ => text_editor::Edit::Paste(Arc::new(inserted.to_string())),
+            }));
+            self.is_dirty = true;
+        }
+
+        self.walk(Syntax::position(text, cursor.min(text.len())));
+        self.refresh();
+    }
+
+    // re-indent to text, keeping the cursor on the same character of its
+    // line
+    pub fn apply_indent(&mut self, text: &str) {
+        let (line, column) = self.cursor();
+        let indent = |text: &str| {
+            text.split('\n')
+                .nth(line)
+                .map_or(0, |line| line.len() - line.trim_start().len())
+        };
+
+        let before = indent(&self.content.text());
+        let after = indent(text);
+
+        self.patch(
+            text,
+            Syntax::offset(text, line, column.max(before) - before + after),
+        );
+    }
+
+    pub fn balance(&self) -> Option<String> {
+        match (self.unclosed, self.unopened) {
+            (0, 0) => None,
+            (unclosed, 0) => Some(format!("{unclosed} unclosed")),
+            (0, unopened) => Some(format!("{unopened} unopened")),
+            (unclosed, unopened) => Some(format!("{unclosed} unclosed, {unopened} unopened")),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match &self.path {
+            Some(path) => path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.display().to_string()),
+            None => "untitled".into(),
+        }
+    }
+
+    // a fresh buffer nobody has typed in can be replaced without asking
+    pub fn is_scratch(&self) -> bool {
+        self.path.is_none() && !self.is_dirty && self.content.text().trim().is_empty()
+    }
+}
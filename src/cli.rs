@@ -0,0 +1,238 @@
+//  SPDX-FileCopyrightText: Copyright 2025 James M. Putnam (putnamjm.design@gmail.com)
+//  SPDX-License-Identifier: MIT
+
+//! command line
+use {
+    crate::{
+        config::Config,
+        image::{
+            exception::{Exception, Phase},
+            mu::Mu,
+            repl::Repl,
+        },
+    },
+    std::{path::PathBuf, process::ExitCode},
+};
+
+pub const USAGE: &str = "\
+usage: rebel [options] [file...]
+
+  --repl          run the terminal listener instead of the gui
+  --eval FORM     evaluate FORM and print its value
+  --load FILE     load FILE
+  --config PATH   read the config from PATH instead of the usual files
+  --set KEY=VALUE override a config setting, VALUE is JSON or a string
+  --show-config   print the effective config and where each value came from
+  --help          print this message
+  --version       print the rebel version
+
+--eval and --load may be repeated and run in order, stopping at the first
+exception. they run without the gui, followed by the listener if --repl
+is also given. files are opened in the gui, so they can't be given with
+--repl, --eval or --load. the exit status is 1 if the environment started
+degraded or anything failed.";
+
+// run without the gui, in command line order
+#[derive(Debug, Clone)]
+pub enum Batch {
+    Eval(String),
+    Load(PathBuf),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    pub batch: Vec<Batch>,
+    pub config: Option<PathBuf>,
+    pub files: Vec<PathBuf>,
+    pub help: bool,
+    pub overrides: Vec<(String, String)>,
+    pub repl: bool,
+    pub show_config: bool,
+    pub version: bool,
+}
+
+impl Options {
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Self::default();
+
+        while let Some(arg) = args.next() {
+            let mut value = |option: &str| {
+                args.next()
+                    .ok_or_else(|| format!("{option} needs an argument"))
+            };
+
+            match arg.as_str() {
+                "--repl" => options.repl = true,
+                "--eval" => options.batch.push(Batch::Eval(value(&arg)?)),
+                "--load" => options.batch.push(Batch::Load(value(&arg)?.into())),
+                "--config" => options.config = Some(value(&arg)?.into()),
+                "--set" => {
+                    let setting = value(&arg)?;
+
+                    match setting.split_once('=') {
+                        Some((key, value)) => options.overrides.push((key.into(), value.into())),
+                        None => return Err(format!("--set {setting}: expected KEY=VALUE")),
+                    }
+                }
+                "--show-config" => options.show_config = true,
+                "-h" | "--help" => options.help = true,
+                "--version" => options.version = true,
+                "--" => {
+                    options.files.extend(args.by_ref().map(PathBuf::from));
+                    break;
+                }
+                _ if arg.starts_with('-') => return Err(format!("unknown option {arg}")),
+                _ => options.files.push(arg.into()),
+            }
+        }
+
+        if !options.files.is_empty() && (options.repl || !options.batch.is_empty()) {
+            return Err(
+                "files open in the gui, they can't be given with --repl, --eval or --load".into(),
+            );
+        }
+
+        Ok(options)
+    }
+
+    pub fn is_gui(&self) -> bool {
+        !self.repl && !self.show_config && self.batch.is_empty()
+    }
+}
+
+pub struct Cli;
+
+impl Cli {
+    // the exit status is 1 if startup was degraded, or if anything raised
+    // an exception or didn't load. a degraded environment still runs the
+    // batch and the listener.
+    pub fn run(options: &Options, config: &Config) -> ExitCode {
+        let mu = Mu::new(config);
+        let ns = mu.env.ns.clone();
+
+        for load in mu.env.startup.failures() {
+            if let Err(why) = &load.result {
+                eprintln!("rebel: {}: {why}", load.name)
+            }
+        }
+
+        for batch in &options.batch {
+            let failed = match batch {
+                Batch::Eval(form) => {
+                    let results = mu.eval_forms(&ns, form);
+
+                    for result in &results {
+                        match &result.value {
+                            Ok(value) => println!("{value}"),
+                            Err(ex) => eprintln!("rebel: {ex}"),
+                        }
+                    }
+
+                    results.iter().any(|result| result.value.is_err())
+                }
+                Batch::Load(path) => match mu.load(&path.to_string_lossy()) {
+                    Ok(true) => false,
+                    Ok(false) => {
+                        eprintln!("rebel: {} didn't load", path.display());
+                        true
+                    }
+                    Err(ex) => {
+                        eprintln!("rebel: can't load {}: {ex}", path.display());
+                        true
+                    }
+                },
+            };
+
+            if failed {
+                return ExitCode::FAILURE;
+            }
+        }
+
+        if options.repl
+            && let Err(ex) = Repl::listener(&mu)
+        {
+            eprintln!(
+                "rebel: listener: {}",
+                Exception::new(mu.env.env, Phase::Eval, ex)
+            );
+
+            return ExitCode::FAILURE;
+        }
+
+        match mu.env.startup.is_degraded() {
+            true => ExitCode::FAILURE,
+            false => ExitCode::SUCCESS,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn batch_in_order() {
+        let options = parse(&["--load", "a.l", "--eval", "(b)", "--load", "c.l"]).unwrap();
+
+        assert!(matches!(
+            options.batch.as_slice(),
+            [Batch::Load(a), Batch::Eval(b), Batch::Load(c)]
+                if a == &PathBuf::from("a.l") && b == "(b)" && c == &PathBuf::from("c.l")
+        ));
+        assert!(!options.is_gui());
+    }
+
+    #[test]
+    fn settings() {
+        let options = parse(&["--set", "rainbow=false", "--set", "rc=a=b"]).unwrap();
+
+        assert_eq!(
+            options.overrides,
+            [
+                ("rainbow".to_string(), "false".to_string()),
+                ("rc".to_string(), "a=b".to_string())
+            ]
+        );
+        assert_eq!(
+            parse(&["--set", "rainbow"]).unwrap_err(),
+            "--set rainbow: expected KEY=VALUE"
+        );
+    }
+
+    #[test]
+    fn flags_and_files() {
+        let options = parse(&["--show-config", "a.l", "--", "--b.l"]).unwrap();
+
+        assert!(options.show_config);
+        assert_eq!(
+            options.files,
+            [PathBuf::from("a.l"), PathBuf::from("--b.l")]
+        );
+        assert!(!options.is_gui());
+
+        let options = parse(&["--config", "rebel.json", "a.l"]).unwrap();
+
+        assert_eq!(options.config, Some(PathBuf::from("rebel.json")));
+        assert!(options.is_gui());
+        assert!(parse(&["-h"]).unwrap().help);
+        assert!(parse(&["--version"]).unwrap().version);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse(&["--bogus"]).unwrap_err(), "unknown option --bogus");
+        assert_eq!(parse(&["--eval"]).unwrap_err(), "--eval needs an argument");
+        assert_eq!(parse(&["--load"]).unwrap_err(), "--load needs an argument");
+        assert_eq!(parse(&["--set"]).unwrap_err(), "--set needs an argument");
+
+        let files = "files open in the gui, they can't be given with --repl, --eval or --load";
+
+        assert_eq!(parse(&["--repl", "a.l"]).unwrap_err(), files);
+        assert_eq!(parse(&["a.l", "--eval", "(b)"]).unwrap_err(), files);
+        assert_eq!(parse(&["--load", "a.l", "b.l"]).unwrap_err(), files);
+    }
+}
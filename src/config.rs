@@ -1,113 +1,1045 @@
 //  SPDX-FileCopyrightText: Copyright 2025 James M. Putnam (putnamjm.design@gmail.com)
 //  SPDX-License-Identifier: MIT
-#![allow(dead_code)]
 use {
+    crate::image::modules::Constraint,
     json::{self, JsonValue},
-    std::fs,
-    std::path::PathBuf,
+    std::{
+        collections::BTreeMap,
+        fmt, fs, io,
+        path::{Path, PathBuf},
+        time::Duration,
+    },
 };
 
-#[derive(Debug, Clone)]
-pub enum Config {
-    Json(JsonValue),
-    None,
+// the built-in namespace presets, each the system files and modules to
+// load in order. the last one loaded names the namespace.
+pub const PRESETS: &[(&str, &[&str])] = &[
+    ("mu", &[]),
+    ("core", &["core.sys"]),
+    ("common", &["core.sys", "common.fasl"]),
+    ("prelude", &["core.sys", "prelude.fasl"]),
+];
+
+// where mu installs its system images and modules
+pub const LIB_PATH: &str = "/opt/mu/lib";
+
+const KEYS: &[&str] = &[
+    "config",
+    "environments",
+    "eval-timeout",
+    "lib-path",
+    "module-versions",
+    "modules",
+    "namespace",
+    "presets",
+    "rainbow",
+    "rc",
+];
+
+// the keys a named environment can set for itself, the rest are the gui's
+const ENVIRONMENT_KEYS: &[&str] = &[
+    "config",
+    "lib-path",
+    "module-versions",
+    "modules",
+    "namespace",
+    "presets",
+    "rc",
+];
+
+// the environment built from the top-level settings
+pub const DEFAULT_ENVIRONMENT: &str = "default";
+
+// where a setting came from, later layers override earlier ones
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    File(PathBuf),
+    Env(String),
+    Cli,
 }
 
-/*
-pub enum JsonValue {
-    Null,
-    Short(Short),
-    String(String),
-    Number(Number),
-    Boolean(bool),
-    Object(Object),
-    Array(Vec<JsonValue>),
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
 }
-*/
 
+// something wrong with a config layer, a bad value leaves the setting as
+// the layers below had it
 #[derive(Debug, Clone)]
-pub enum ConfigOpt {
-    Array(Vec<ConfigOpt>),
-    Boolean(bool),
-    Null,
-    Number(i32),
-    Object(String),
-    String(String),
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub source: Source,
+    pub key: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    // mu environment options, as the JSON object mu expects
+    pub env: Option<String>,
+    // the preset the environment is built from
+    pub namespace: String,
+    pub presets: BTreeMap<String, Vec<String>>,
+    // directories searched in order for system images and modules
+    pub lib_path: Vec<PathBuf>,
+    pub modules: Vec<String>,
+    // version constraints on modules, by namespace
+    pub module_versions: BTreeMap<String, Constraint>,
+    pub rc: Option<String>,
+    pub eval_timeout: Option<Duration>,
+    pub rainbow: bool,
+    // named environments, each a set of ENVIRONMENT_KEYS laid over the
+    // top-level settings
+    pub environments: BTreeMap<String, JsonValue>,
+    pub sources: BTreeMap<String, Source>,
+    pub diagnostics: Vec<Diagnostic>,
+    // the namespaces the layers below set, for when a layer names one no
+    // preset defines
+    namespaces_below: Vec<(String, Option<Source>)>,
+    // what this config was built from, to build it again
+    path: Option<PathBuf>,
+    overrides: Vec<(String, String)>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            env: None,
+            namespace: "mu".into(),
+            presets: PRESETS
+                .iter()
+                .map(|(name, files)| {
+                    (
+                        name.to_string(),
+                        files.iter().map(|file| file.to_string()).collect(),
+                    )
+                })
+                .collect(),
+            lib_path: vec![PathBuf::from(LIB_PATH)],
+            modules: Vec::new(),
+            module_versions: BTreeMap::new(),
+            rc: None,
+            eval_timeout: None,
+            rainbow: false,
+            environments: BTreeMap::new(),
+            sources: BTreeMap::new(),
+            diagnostics: Vec::new(),
+            namespaces_below: Vec::new(),
+            path: None,
+            overrides: Vec::new(),
+        }
+    }
 }
 
 impl Config {
-    // if we have a .rebel in the current directory, use it.
-    // otherwise, see if there's one in the home directory
-    pub fn new() -> Self {
-        let mut home_path = PathBuf::new();
-
-        home_path.push(std::env::home_dir().unwrap().as_path());
-        home_path.push(".rebel");
-
-        let mut cwd_path = PathBuf::new();
-
-        cwd_path.push(std::env::current_dir().unwrap().as_path());
-        cwd_path.push(".rebel");
-
-        match fs::read_to_string(cwd_path) {
-            Ok(json) => match json::parse(&json) {
-                Ok(opts) => Config::Json(opts),
-                Err(_) => Config::None,
-            },
-            Err(_) => match fs::read_to_string(home_path) {
-                Ok(json) => match json::parse(&json) {
-                    Ok(opts) => Config::Json(opts),
-                    Err(_) => {
-                        eprintln!("rebel: failed to parse config JSON, using null config");
-                        Config::None
+    // built-in defaults, then $XDG_CONFIG_HOME/rebel, ~/.rebel and the
+    // project's ./.rebel, then REBEL_* environment variables, then the
+    // command line. a config file named on the command line replaces the
+    // searched files and has to be usable.
+    pub fn new(path: Option<&Path>, overrides: &[(String, String)]) -> Result<Self, String> {
+        Self::with_env(path, overrides, &Self::vars())
+    }
+
+    // as new, with vars in place of the process's environment variables
+    pub fn with_env(
+        path: Option<&Path>,
+        overrides: &[(String, String)],
+        vars: &BTreeMap<String, String>,
+    ) -> Result<Self, String> {
+        let mut config = Self {
+            path: path.map(PathBuf::from),
+            overrides: overrides.to_vec(),
+            ..Self::default()
+        };
+
+        match path {
+            Some(path) => {
+                let json = fs::read_to_string(path)
+                    .map_err(|err| format!("can't read config {}: {err}", path.display()))?;
+
+                config
+                    .apply_file(path, &json)
+                    .map_err(|err| format!("config {}: {err}", path.display()))?
+            }
+            None => {
+                for path in Self::files(vars) {
+                    match fs::read_to_string(&path) {
+                        Ok(json) => {
+                            if let Err(message) = config.apply_file(&path, &json) {
+                                config.diagnose(Source::File(path), Severity::Error, None, message)
+                            }
+                        }
+                        Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+                        Err(err) => config.diagnose(
+                            Source::File(path),
+                            Severity::Error,
+                            None,
+                            format!("can't read: {err}"),
+                        ),
                     }
-                },
-                Err(_) => Config::None,
-            },
-        }
-    }
-
-    fn json_opt(&self, value: &JsonValue) -> ConfigOpt {
-        match value {
-            JsonValue::Short(str) => ConfigOpt::String(str.as_str().to_string()),
-            JsonValue::String(str) => ConfigOpt::String(str.clone()),
-            JsonValue::Object(obj) => ConfigOpt::Object(obj.dump()),
-            JsonValue::Boolean(bool) => ConfigOpt::Boolean(*bool),
-            JsonValue::Number(_number) => panic!(),
-            JsonValue::Array(arr) => ConfigOpt::Array(
-                arr.iter()
-                    .map(|json_value| self.json_opt(json_value))
-                    .collect::<Vec<ConfigOpt>>(),
-            ),
-            JsonValue::Null => ConfigOpt::Null,
+                }
+            }
+        }
+
+        // mu's own variable, REBEL_LIB_PATH overrides it
+        if let Some(dirs) = vars.get("MU_LIB_PATH") {
+            let dirs = std::env::split_paths(dirs)
+                .map(|dir| dir.to_string_lossy().into_owned())
+                .collect::<Vec<String>>();
+
+            config.apply("lib-path", &dirs.into(), Source::Env("MU_LIB_PATH".into()))
+        }
+
+        for key in KEYS {
+            let var = format!("REBEL_{}", key.to_uppercase().replace('-', "_"));
+
+            if let Some(text) = vars.get(&var) {
+                config.apply(key, &Self::text_value(key, text), Source::Env(var))
+            }
         }
+
+        for (key, text) in overrides {
+            if KEYS.contains(&key.as_str()) {
+                config.apply(key, &Self::text_value(key, text), Source::Cli)
+            } else {
+                config.diagnose(
+                    Source::Cli,
+                    Severity::Warning,
+                    Some(key),
+                    "unknown key, ignored".into(),
+                )
+            }
+        }
+
+        config.check_namespace();
+
+        Ok(config)
     }
 
-    pub fn map(&self, key: &str) -> Option<String> {
-        match self {
-            Config::Json(opts) => match &opts[key] {
-                JsonValue::Short(str) => Some(str.as_str().to_string()),
-                JsonValue::String(str) => Some(str.as_str().to_string()),
-                JsonValue::Object(obj) => Some(obj.dump()),
-                JsonValue::Array(arr) => Some(
-                    arr.iter()
-                        .map(|module| match module {
-                            JsonValue::Short(str) => str.as_str().to_string(),
-                            JsonValue::String(str) => str.as_str().to_string(),
-                            _ => panic!(),
-                        })
-                        .collect::<String>(),
-                ),
-                _ => None,
-            },
-            Config::None => None,
-        }
-    }
-
-    pub fn map_opt(&self, key: &str) -> Option<ConfigOpt> {
+    // read the same files, variables and overrides again, picking up
+    // whatever changed since
+    pub fn reload(&self) -> Result<Self, String> {
+        self.reload_with_env(&Self::vars())
+    }
+
+    pub fn reload_with_env(&self, vars: &BTreeMap<String, String>) -> Result<Self, String> {
+        Self::with_env(self.path.as_deref(), &self.overrides, vars)
+    }
+
+    // the process's environment variables, any that aren't unicode are
+    // left out
+    fn vars() -> BTreeMap<String, String> {
+        std::env::vars_os()
+            .filter_map(|(var, value)| Some((var.into_string().ok()?, value.into_string().ok()?)))
+            .collect()
+    }
+
+    // the config files to search, lowest precedence first
+    fn files(vars: &BTreeMap<String, String>) -> Vec<PathBuf> {
+        let home = std::env::home_dir();
+        let xdg = match vars.get("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+            _ => home.as_ref().map(|home| home.join(".config")),
+        };
+
+        let mut files = Vec::<PathBuf>::new();
+
+        for path in [
+            xdg.map(|dir| dir.join("rebel")),
+            home.map(|home| home.join(".rebel")),
+            std::env::current_dir().ok().map(|cwd| cwd.join(".rebel")),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            // working in the home directory reads ~/.rebel once
+            if !files.contains(&path) {
+                files.push(path)
+            }
+        }
+
+        files
+    }
+
+    // the default environment first, then the named ones. a named
+    // environment's bad values are its own diagnostics.
+    pub fn environments(&self) -> Vec<(String, Self)> {
+        let source = self.source("environments").clone();
+        let base = Self {
+            environments: BTreeMap::new(),
+            diagnostics: Vec::new(),
+            ..self.clone()
+        };
+
+        let mut environments = vec![(DEFAULT_ENVIRONMENT.to_string(), base.clone())];
+
+        for (name, settings) in &self.environments {
+            let mut config = base.clone();
+
+            for (key, value) in settings.entries() {
+                config.apply(key, value, source.clone())
+            }
+
+            config.check_namespace();
+
+            for diagnostic in &mut config.diagnostics {
+                diagnostic.key = Some(match &diagnostic.key {
+                    Some(key) => format!("environments.{name}.{key}"),
+                    None => format!("environments.{name}"),
+                })
+            }
+
+            environments.push((name.clone(), config))
+        }
+
+        environments
+    }
+
+    // presets can come from any layer, so the namespace is checked once
+    // they're all in. an unknown one falls back to the layer below, and
+    // past the bottom layer to mu, which can't be removed.
+    fn check_namespace(&mut self) {
+        while !self.presets.contains_key(&self.namespace) {
+            let message = format!(
+                "unknown namespace {}, expected one of {}",
+                self.namespace,
+                self.presets
+                    .keys()
+                    .map(String::as_str)
+                    .collect::<Vec<&str>>()
+                    .join(", ")
+            );
+
+            self.diagnose(
+                self.source("namespace").clone(),
+                Severity::Error,
+                Some("namespace"),
+                message,
+            );
+
+            let (namespace, source) = match self.namespaces_below.pop() {
+                Some(below) => below,
+                None => {
+                    let default = Self::default().namespace;
+
+                    self.presets.entry(default.clone()).or_default();
+                    (default, None)
+                }
+            };
+
+            self.namespace = namespace;
+
+            match source {
+                Some(source) => self.sources.insert("namespace".into(), source),
+                None => self.sources.remove("namespace"),
+            };
+        }
+    }
+
+    pub fn source(&self, key: &str) -> &Source {
+        self.sources.get(key).unwrap_or(&Source::Default)
+    }
+
+    // the effective config, with the layer each value came from
+    pub fn report(&self) -> String {
+        let width = KEYS.iter().map(|key| key.len()).max().unwrap_or(0);
+
+        KEYS.iter()
+            .map(|key| {
+                format!(
+                    "{key:width$}  {}  ({})\n",
+                    self.value(key).dump(),
+                    self.source(key)
+                )
+            })
+            .collect()
+    }
+
+    fn value(&self, key: &str) -> JsonValue {
+        match key {
+            "config" => self
+                .env
+                .as_ref()
+                .and_then(|env| json::parse(env).ok())
+                .unwrap_or(JsonValue::Null),
+            "environments" => {
+                let mut environments = JsonValue::new_object();
+
+                for (name, settings) in &self.environments {
+                    let _ = environments.insert(name, settings.clone());
+                }
+
+                environments
+            }
+            "eval-timeout" => self
+                .eval_timeout
+                .map_or(JsonValue::Null, |timeout| timeout.as_secs().into()),
+            "lib-path" => self
+                .lib_path
+                .iter()
+                .map(|dir| dir.to_string_lossy().into_owned())
+                .collect::<Vec<String>>()
+                .into(),
+            "module-versions" => {
+                let mut versions = JsonValue::new_object();
+
+                for (module, constraint) in &self.module_versions {
+                    let _ = versions.insert(module, constraint.to_string());
+                }
+
+                versions
+            }
+            "modules" => self.modules.clone().into(),
+            "namespace" => self.namespace.clone().into(),
+            "presets" => {
+                let mut presets = JsonValue::new_object();
+
+                for (name, files) in &self.presets {
+                    let _ = presets.insert(name, files.clone());
+                }
+
+                presets
+            }
+            "rainbow" => self.rainbow.into(),
+            "rc" => self.rc.clone().into(),
+            _ => JsonValue::Null,
+        }
+    }
+
+    fn diagnose(&mut self, source: Source, severity: Severity, key: Option<&str>, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            source,
+            key: key.map(String::from),
+            message,
+        })
+    }
+
+    // a file that isn't a JSON object is unusable as a whole
+    fn apply_file(&mut self, path: &Path, json: &str) -> Result<(), String> {
+        let object = match json::parse(json) {
+            Ok(JsonValue::Object(object)) => object,
+            Ok(_) => return Err("expected a JSON object".into()),
+            Err(err) => return Err(format!("not JSON: {err}")),
+        };
+
+        for (key, value) in object.iter() {
+            let source = Source::File(path.into());
+
+            if KEYS.contains(&key) {
+                self.apply(key, value, source)
+            } else {
+                self.diagnose(
+                    source,
+                    Severity::Warning,
+                    Some(key),
+                    "unknown key, ignored".into(),
+                )
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply(&mut self, key: &str, value: &JsonValue, source: Source) {
+        if key == "namespace" {
+            self.namespaces_below
+                .push((self.namespace.clone(), self.sources.get(key).cloned()))
+        }
+
+        match self.set(key, value, &source) {
+            Ok(()) => {
+                self.sources.insert(key.into(), source);
+            }
+            Err(message) => self.diagnose(source, Severity::Error, Some(key), message),
+        }
+    }
+
+    // environment variables and command line values are JSON if they
+    // parse as JSON, modules may also be separated by commas and the
+    // lib-path like PATH
+    fn text_value(key: &str, text: &str) -> JsonValue {
+        match json::parse(text) {
+            Ok(value) => value,
+            Err(_) if key == "lib-path" => std::env::split_paths(text)
+                .map(|dir| dir.to_string_lossy().into_owned())
+                .collect::<Vec<String>>()
+                .into(),
+            Err(_) if key == "modules" => text
+                .split(',')
+                .map(str::trim)
+                .filter(|module| !module.is_empty())
+                .collect::<Vec<&str>>()
+                .into(),
+            Err(_) => text.into(),
+        }
+    }
+
+    // ~/ names the home directory, the shell isn't there to expand it
+    fn expand_home(dir: &str) -> PathBuf {
+        match (dir.strip_prefix("~/"), std::env::home_dir()) {
+            (Some(rest), Some(home)) => home.join(rest),
+            _ => PathBuf::from(dir),
+        }
+    }
+
+    // validate one known key, null resets it to the default. a layer's
+    // object merges into what the layers below set, entry by entry, with
+    // a null entry removing one. anything else replaces the value whole.
+    fn set(&mut self, key: &str, value: &JsonValue, source: &Source) -> Result<(), String> {
+        if value.is_null() {
+            let default = Self::default();
+
+            match key {
+                "config" => self.env = default.env,
+                "environments" => self.environments = default.environments,
+                "eval-timeout" => self.eval_timeout = default.eval_timeout,
+                "lib-path" => self.lib_path = default.lib_path,
+                "module-versions" => self.module_versions = default.module_versions,
+                "modules" => self.modules = default.modules,
+                "namespace" => self.namespace = default.namespace,
+                "presets" => self.presets = default.presets,
+                "rainbow" => self.rainbow = default.rainbow,
+                "rc" => self.rc = default.rc,
+                _ => (),
+            }
+
+            return Ok(());
+        }
+
+        match key {
+            "config" => {
+                let JsonValue::Object(options) = value else {
+                    return Err("expected an object of mu environment options".into());
+                };
+
+                let mut env = self
+                    .env
+                    .as_deref()
+                    .and_then(|env| json::parse(env).ok())
+                    .unwrap_or_else(JsonValue::new_object);
+
+                for (option, value) in options.iter() {
+                    if value.is_null() {
+                        env.remove(option);
+                        continue;
+                    }
+
+                    let value = match value {
+                        JsonValue::Number(_) => value.dump(),
+                        _ => match value.as_str() {
+                            Some(value) => value.into(),
+                            None => {
+                                self.diagnose(
+                                    source.clone(),
+                                    Severity::Error,
+                                    Some(&format!("config.{option}")),
+                                    "expected a string or a number, skipped".into(),
+                                );
+                                continue;
+                            }
+                        },
+                    };
+
+                    let _ = env.insert(option, value);
+                }
+
+                self.env = (!env.is_empty()).then(|| env.dump())
+            }
+            "environments" => {
+                let JsonValue::Object(environments) = value else {
+                    return Err("expected an object of named environments".into());
+                };
+
+                for (name, settings) in environments.iter() {
+                    let key = format!("environments.{name}");
+
+                    if settings.is_null() {
+                        self.environments.remove(name);
+                        continue;
+                    }
+
+                    let problem = if name == DEFAULT_ENVIRONMENT {
+                        Some("the default environment is the top-level settings, skipped")
+                    } else if !settings.is_object() {
+                        Some("expected an object of environment settings, skipped")
+                    } else {
+                        None
+                    };
+
+                    if let Some(message) = problem {
+                        self.diagnose(source.clone(), Severity::Error, Some(&key), message.into());
+                        continue;
+                    }
+
+                    let mut environment = JsonValue::new_object();
+
+                    for (setting, value) in settings.entries() {
+                        if ENVIRONMENT_KEYS.contains(&setting) {
+                            let _ = environment.insert(setting, value.clone());
+                        } else {
+                            self.diagnose(
+                                source.clone(),
+                                Severity::Warning,
+                                Some(&format!("{key}.{setting}")),
+                                format!(
+                                    "not an environment setting, expected one of {}, ignored",
+                                    ENVIRONMENT_KEYS.join(", ")
+                                ),
+                            )
+                        }
+                    }
+
+                    self.environments.insert(name.into(), environment);
+                }
+            }
+            "namespace" => {
+                self.namespace = value
+                    .as_str()
+                    .ok_or("expected the name of a namespace preset")?
+                    .into()
+            }
+            "presets" => {
+                let JsonValue::Object(presets) = value else {
+                    return Err("expected an object of namespace presets".into());
+                };
+
+                for (name, files) in presets.iter() {
+                    if files.is_null() && name == Self::default().namespace {
+                        self.diagnose(
+                            source.clone(),
+                            Severity::Error,
+                            Some(&format!("presets.{name}")),
+                            "the default preset can't be removed, skipped".into(),
+                        );
+                        continue;
+                    }
+
+                    if files.is_null() {
+                        self.presets.remove(name);
+                        continue;
+                    }
+
+                    let files = match files {
+                        JsonValue::Array(files) => files
+                            .iter()
+                            .map(|file| file.as_str().map(String::from))
+                            .collect::<Option<Vec<String>>>(),
+                        _ => None,
+                    };
+
+                    match files {
+                        Some(files) => {
+                            self.presets.insert(name.into(), files);
+                        }
+                        None => self.diagnose(
+                            source.clone(),
+                            Severity::Error,
+                            Some(&format!("presets.{name}")),
+                            "expected an array of system file and module names, skipped".into(),
+                        ),
+                    }
+                }
+            }
+            "modules" => {
+                let JsonValue::Array(modules) = value else {
+                    return Err("expected an array of module file names".into());
+                };
+
+                self.modules.clear();
+
+                for (nth, module) in modules.iter().enumerate() {
+                    match module.as_str() {
+                        Some(module) => self.modules.push(module.into()),
+                        None => self.diagnose(
+                            source.clone(),
+                            Severity::Error,
+                            Some(&format!("modules[{nth}]")),
+                            "expected a module file name, skipped".into(),
+                        ),
+                    }
+                }
+            }
+            "module-versions" => {
+                let JsonValue::Object(versions) = value else {
+                    return Err("expected an object of module version constraints".into());
+                };
+
+                for (module, constraint) in versions.iter() {
+                    if constraint.is_null() {
+                        self.module_versions.remove(module);
+                        continue;
+                    }
+
+                    let constraint = constraint
+                        .as_str()
+                        .ok_or_else(|| "expected a version constraint like \">=0.2, <1\"".into())
+                        .and_then(Constraint::parse);
+
+                    match constraint {
+                        Ok(constraint) => {
+                            self.module_versions.insert(module.into(), constraint);
+                        }
+                        Err(message) => self.diagnose(
+                            source.clone(),
+                            Severity::Error,
+                            Some(&format!("module-versions.{module}")),
+                            format!("{message}, skipped"),
+                        ),
+                    }
+                }
+            }
+            "lib-path" => {
+                let JsonValue::Array(dirs) = value else {
+                    return Err("expected an array of directories".into());
+                };
+
+                self.lib_path.clear();
+
+                for (nth, dir) in dirs.iter().enumerate() {
+                    match dir.as_str() {
+                        Some(dir) => self.lib_path.push(Self::expand_home(dir)),
+                        None => self.diagnose(
+                            source.clone(),
+                            Severity::Error,
+                            Some(&format!("lib-path[{nth}]")),
+                            "expected a directory, skipped".into(),
+                        ),
+                    }
+                }
+            }
+            "rc" => {
+                self.rc = Some(
+                    value
+                        .as_str()
+                        .ok_or("expected the path of a file to load")?
+                        .into(),
+                )
+            }
+            "eval-timeout" => {
+                let secs = value
+                    .as_u64()
+                    .ok_or("expected a whole number of seconds, 0 or null to wait forever")?;
+
+                self.eval_timeout = (secs > 0).then(|| Duration::from_secs(secs))
+            }
+            "rainbow" => self.rainbow = value.as_bool().ok_or("expected true or false")?,
+            _ => (),
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Config::Json(opts) => Some(self.json_opt(&opts[key])),
-            Config::None => None,
+            Source::Default => f.write_str("default"),
+            Source::File(path) => write!(f, "{}", path.display()),
+            Source::Env(var) => write!(f, "${var}"),
+            Source::Cli => f.write_str("command line"),
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.key {
+            Some(key) => write!(
+                f,
+                "{}: {}: {key}: {}",
+                self.severity, self.source, self.message
+            ),
+            None => write!(f, "{}: {}: {}", self.severity, self.source, self.message),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::temp_dir::TempDir};
+
+    // the config from path, without the process's environment variables
+    fn read(path: &Path, overrides: &[(String, String)]) -> Result<Config, String> {
+        Config::with_env(Some(path), overrides, &BTreeMap::new())
+    }
+
+    fn set(overrides: &[(&str, &str)]) -> Vec<(String, String)> {
+        overrides
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn keys(config: &Config) -> Vec<(Severity, String)> {
+        config
+            .diagnostics
+            .iter()
+            .map(|diagnostic| {
+                (
+                    diagnostic.severity,
+                    diagnostic.key.clone().unwrap_or_default(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn layers() {
+        let dir = TempDir::new("config-layers");
+        let path = dir.file(
+            "rebel.json",
+            r#"{ "rainbow": true, "eval-timeout": 10, "namespace": "core" }"#,
+        );
+        let config = read(&path, &set(&[("rainbow", "false")])).unwrap();
+
+        assert!(!config.rainbow);
+        assert_eq!(config.source("rainbow"), &Source::Cli);
+        assert_eq!(config.eval_timeout, Some(Duration::from_secs(10)));
+        assert_eq!(config.source("eval-timeout"), &Source::File(path.clone()));
+        assert_eq!(config.namespace, "core");
+        assert_eq!(config.source("lib-path"), &Source::Default);
+        assert!(config.diagnostics.is_empty());
+
+        let report = config.report();
+
+        assert!(
+            report
+                .lines()
+                .any(|line| line.starts_with("rainbow") && line.ends_with("false  (command line)"))
+        );
+        assert!(
+            report
+                .lines()
+                .any(|line| line.starts_with("eval-timeout") && line.contains("10"))
+        );
+    }
+
+    #[test]
+    fn variables() {
+        let dir = TempDir::new("config-variables");
+        let path = dir.file("rebel.json", r#"{ "rainbow": true, "eval-timeout": 10 }"#);
+        let vars = [
+            ("MU_LIB_PATH", "/a:/b"),
+            ("REBEL_RAINBOW", "false"),
+            ("REBEL_EVAL_TIMEOUT", "3"),
+            ("REBEL_NOPE", "1"),
+        ]
+        .into_iter()
+        .map(|(var, value)| (var.to_string(), value.to_string()))
+        .collect::<BTreeMap<String, String>>();
+
+        let config = Config::with_env(Some(&path), &set(&[("eval-timeout", "4")]), &vars).unwrap();
+
+        assert_eq!(config.lib_path, [PathBuf::from("/a"), PathBuf::from("/b")]);
+        assert_eq!(
+            config.source("lib-path"),
+            &Source::Env("MU_LIB_PATH".into())
+        );
+        assert!(!config.rainbow);
+        assert_eq!(
+            config.source("rainbow"),
+            &Source::Env("REBEL_RAINBOW".into())
+        );
+        assert_eq!(config.eval_timeout, Some(Duration::from_secs(4)));
+        assert_eq!(config.source("eval-timeout"), &Source::Cli);
+        assert!(config.diagnostics.is_empty());
+
+        // the variables are read again on reload
+        let reloaded = config.reload_with_env(&BTreeMap::new()).unwrap();
+
+        assert!(reloaded.rainbow);
+        assert_eq!(reloaded.source("lib-path"), &Source::Default);
+    }
+
+    #[test]
+    fn bad_values() {
+        let dir = TempDir::new("config-bad-values");
+        let path = dir.file(
+            "rebel.json",
+            r#"{ "rainbow": "yes", "eval-timeout": -1, "bogus": 1, "modules": ["a", 2] }"#,
+        );
+        let config = read(&path, &set(&[("nope", "1")])).unwrap();
+
+        assert!(!config.rainbow);
+        assert_eq!(config.source("rainbow"), &Source::Default);
+        assert_eq!(config.eval_timeout, None);
+        assert_eq!(config.modules, ["a"]);
+        assert_eq!(
+            keys(&config),
+            [
+                (Severity::Error, "rainbow".to_string()),
+                (Severity::Error, "eval-timeout".to_string()),
+                (Severity::Warning, "bogus".to_string()),
+                (Severity::Error, "modules[1]".to_string()),
+                (Severity::Warning, "nope".to_string()),
+            ]
+        );
+
+        // a file named on the command line has to be usable
+        assert!(read(&dir.file("not-object.json", "[1]"), &[]).is_err());
+        assert!(read(&dir.file("not-json.json", "{"), &[]).is_err());
+    }
+
+    #[test]
+    fn reload() {
+        let dir = TempDir::new("config-reload");
+        let path = dir.file("rebel.json", r#"{ "rainbow": false }"#);
+        let config = read(&path, &set(&[("eval-timeout", "5")])).unwrap();
+
+        fs::write(&path, r#"{ "rainbow": true, "eval-timeout": 9 }"#).unwrap();
+
+        let reloaded = config.reload_with_env(&BTreeMap::new()).unwrap();
+
+        assert!(reloaded.rainbow);
+        assert_eq!(reloaded.eval_timeout, Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn objects_merge() {
+        let dir = TempDir::new("config-objects-merge");
+        let path = dir.file(
+            "rebel.json",
+            r#"{ "config": { "pages": 1024, "gc-mode": "auto" },
+                 "environments": { "a": { "namespace": "core" }, "b": {} } }"#,
+        );
+        let config = read(
+            &path,
+            &set(&[
+                ("config", r#"{ "pages": "2048", "gc-mode": null }"#),
+                ("environments", r#"{ "b": null, "c": { "rc": "c.l" } }"#),
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(config.env.as_deref(), Some(r#"{"pages":"2048"}"#));
+        assert_eq!(
+            config.environments.keys().collect::<Vec<&String>>(),
+            ["a", "c"]
+        );
+
+        // null for the whole key is the default again
+        let config = read(&path, &set(&[("config", "null")])).unwrap();
+
+        assert_eq!(config.env, None);
+    }
+
+    #[test]
+    fn environments() {
+        let dir = TempDir::new("config-environments");
+        let path = dir.file(
+            "rebel.json",
+            r#"{ "namespace": "core", "rainbow": true,
+                 "environments": { "plain": { "namespace": "mu", "rainbow": false },
+                                   "odd": { "namespace": 1 } } }"#,
+        );
+        let config = read(&path, &[]).unwrap();
+        let environments = config.environments();
+
+        assert_eq!(
+            environments
+                .iter()
+                .map(|(name, config)| (name.as_str(), config.namespace.as_str()))
+                .collect::<Vec<_>>(),
+            [("default", "core"), ("odd", "core"), ("plain", "mu")]
+        );
+        assert_eq!(
+            keys(&config),
+            [(Severity::Warning, "environments.plain.rainbow".to_string())]
+        );
+        assert_eq!(
+            keys(&environments[1].1),
+            [(Severity::Error, "environments.odd.namespace".to_string())]
+        );
+    }
+
+    #[test]
+    fn unknown_namespace() {
+        let dir = TempDir::new("config-unknown-namespace");
+        let path = dir.file("rebel.json", r#"{ "namespace": "core" }"#);
+
+        // back to the layer below
+        let config = read(&path, &set(&[("namespace", "nowhere")])).unwrap();
+
+        assert_eq!(config.namespace, "core");
+        assert_eq!(config.source("namespace"), &Source::File(path.clone()));
+        assert_eq!(keys(&config), [(Severity::Error, "namespace".to_string())]);
+
+        // mu stays, so falling back ends there
+        let config = read(
+            &path,
+            &set(&[
+                ("presets", r#"{ "mu": null, "core": null }"#),
+                ("namespace", "x"),
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(config.namespace, "mu");
+        assert_eq!(config.source("namespace"), &Source::Default);
+        assert_eq!(config.presets.get("mu"), Some(&Vec::new()));
+        assert_eq!(
+            keys(&config),
+            [
+                (Severity::Error, "presets.mu".to_string()),
+                (Severity::Error, "namespace".to_string()),
+                (Severity::Error, "namespace".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn presets_and_versions_merge() {
+        let dir = TempDir::new("config-presets-and-versions");
+        let path = dir.file(
+            "rebel.json",
+            r#"{ "presets": { "mine": ["core.sys", "mine.fasl"], "common": null },
+                 "module-versions": { "deftype": "^0.1", "common": ">=0.2" } }"#,
+        );
+        let config = read(
+            &path,
+            &set(&[
+                ("presets", r#"{ "more": [], "bad": [1] }"#),
+                ("module-versions", r#"{ "common": null, "extra": "bogus" }"#),
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.presets.keys().collect::<Vec<&String>>(),
+            ["core", "mine", "more", "mu", "prelude"]
+        );
+        assert_eq!(config.presets["mine"], ["core.sys", "mine.fasl"]);
+        assert_eq!(
+            config
+                .module_versions
+                .iter()
+                .map(|(module, constraint)| (module.as_str(), constraint.to_string()))
+                .collect::<Vec<_>>(),
+            [("deftype", "^0.1".to_string())]
+        );
+        assert_eq!(
+            keys(&config),
+            [
+                (Severity::Error, "presets.bad".to_string()),
+                (Severity::Error, "module-versions.extra".to_string()),
+            ]
+        );
+
+        // null for the whole key is the default again
+        let config = read(&path, &set(&[("presets", "null")])).unwrap();
+
+        assert_eq!(config.presets.len(), PRESETS.len());
+    }
+}
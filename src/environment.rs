@@ -0,0 +1,101 @@
+//  SPDX-FileCopyrightText: Copyright 2025 James M. Putnam (putnamjm.design@gmail.com)
+//  SPDX-License-Identifier: MIT
+
+//! named environments
+use crate::{
+    config::Config,
+    image::{console::Console, env_::Startup, mu::Mu, worker::Worker},
+    status_line::StatusLine,
+};
+
+// a mu environment on its own worker, and what the gui needs to know
+// about it without asking
+pub struct Environment {
+    pub name: String,
+    pub config: Config,
+    pub console: Console,
+    pub worker: Worker,
+    pub status_line: StatusLine,
+    // the namespace buffers use unless they pick another, and the
+    // namespaces loaded to pick from
+    pub ns: String,
+    pub namespaces: Vec<String>,
+    pub startup: Startup,
+    // whether the fields above are the worker's yet
+    pub started: bool,
+}
+
+// what the worker knows once startup finishes
+#[derive(Debug, Clone)]
+pub struct Started {
+    status_line: StatusLine,
+    ns: String,
+    namespaces: Vec<String>,
+    startup: Startup,
+}
+
+impl Started {
+    pub fn new(mu: &Mu) -> Self {
+        Self {
+            status_line: StatusLine::new(mu),
+            ns: mu.env.ns.clone(),
+            namespaces: mu.env.namespaces.clone(),
+            startup: mu.env.startup.clone(),
+        }
+    }
+}
+
+impl Environment {
+    // the worker starts up on its own thread, the rest is filled in
+    // from a Started job run on it
+    pub fn new(name: &str, config: Config, console: &Console) -> Self {
+        Self {
+            name: name.into(),
+            worker: Worker::new(&config, console),
+            status_line: StatusLine::starting(),
+            ns: "mu".into(),
+            namespaces: vec!["mu".into()],
+            startup: Startup::default(),
+            started: false,
+            config,
+            console: console.clone(),
+        }
+    }
+
+    pub fn started(&mut self, started: Started) {
+        self.status_line = started.status_line;
+        self.ns = started.ns;
+        self.namespaces = started.namespaces;
+        self.startup = started.startup;
+        self.started = true;
+    }
+
+    // every environment the config names, the default first
+    pub fn all(config: &Config, console: &Console) -> Vec<Self> {
+        config
+            .environments()
+            .into_iter()
+            .map(|(name, config)| Self::new(&name, config, console))
+            .collect()
+    }
+
+    // a fresh environment from the same config, and the worker it
+    // replaced
+    pub fn reset(&mut self) -> Worker {
+        std::mem::replace(
+            self,
+            Self::new(&self.name, self.config.clone(), &self.console),
+        )
+        .worker
+    }
+
+    // the namespace a buffer evaluates in here, its own if this
+    // environment has it
+    pub fn ns_for<'a>(&'a self, ns: &'a str) -> &'a str {
+        if self.namespaces.iter().any(|known| known == ns) {
+            ns
+        } else {
+            &self.ns
+        }
+    }
+}
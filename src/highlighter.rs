@@ -0,0 +1,217 @@
+//  SPDX-FileCopyrightText: Copyright 2025 James M. Putnam (putnamjm.design@gmail.com)
+//  SPDX-License-Identifier: MIT
+
+//! mu syntax highlighting
+use {
+    crate::{
+        buffer::BufferId,
+        syntax::{Atom, Context, SPECIAL_FORMS, Syntax, TokenKind},
+    },
+    iced::{
+        Font, Theme,
+        advanced::text::highlighter::{self, Format},
+        font,
+    },
+    std::ops::Range,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Highlight {
+    Char,
+    Comment,
+    // nesting depth, for rainbow delimiters
+    Delimiter(usize),
+    Keyword,
+    Matched,
+    Namespace,
+    Number,
+    Quote,
+    Special,
+    String,
+    Unmatched,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mark {
+    Matched,
+    Unmatched,
+}
+
+// a change of buffer invalidates everything highlighted so far
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Settings {
+    pub buffer: Option<BufferId>,
+    pub rainbow: bool,
+    // delimiters to mark, by line and byte range within the line
+    pub marks: Vec<(usize, Range<usize>, Mark)>,
+}
+
+pub struct Highlighter {
+    settings: Settings,
+    // the scan context and nesting depth at the start of each line
+    contexts: Vec<(Context, usize)>,
+    current_line: usize,
+}
+
+impl Highlighter {
+    pub fn to_format(highlight: &Highlight, theme: &Theme) -> Format<Font> {
+        let palette = theme.extended_palette();
+
+        let rainbow = [
+            palette.primary.base.color,
+            palette.success.base.color,
+            palette.danger.base.color,
+            palette.secondary.strong.color,
+            palette.primary.strong.color,
+            palette.success.strong.color,
+        ];
+
+        let color = match highlight {
+            Highlight::Char => palette.success.strong.color,
+            Highlight::Comment => palette.secondary.base.color,
+            Highlight::Delimiter(depth) => rainbow[depth % rainbow.len()],
+            Highlight::Keyword => palette.primary.strong.color,
+            Highlight::Matched => palette.primary.strong.color,
+            Highlight::Namespace => palette.primary.base.color,
+            Highlight::Number => palette.danger.base.color,
+            Highlight::Quote => palette.danger.strong.color,
+            Highlight::Special => palette.primary.strong.color,
+            Highlight::String => palette.success.base.color,
+            Highlight::Unmatched => palette.danger.strong.color,
+        };
+
+        Format {
+            color: Some(color),
+            font: matches!(
+                highlight,
+                Highlight::Special | Highlight::Matched | Highlight::Unmatched
+            )
+            .then_some(Font {
+                weight: font::Weight::Bold,
+                ..Font::MONOSPACE
+            }),
+        }
+    }
+}
+
+impl highlighter::Highlighter for Highlighter {
+    type Settings = Settings;
+    type Highlight = Highlight;
+    type Iterator<'a> = std::vec::IntoIter<(Range<usize>, Highlight)>;
+
+    fn new(settings: &Self::Settings) -> Self {
+        Self {
+            settings: settings.clone(),
+            contexts: vec![(Context::Code, 0)],
+            current_line: 0,
+        }
+    }
+
+    fn update(&mut self, new_settings: &Self::Settings) {
+        let line = if self.settings.buffer != new_settings.buffer
+            || self.settings.rainbow != new_settings.rainbow
+        {
+            0
+        } else {
+            // only the lines with old or new marks need another look
+            self.settings
+                .marks
+                .iter()
+                .chain(new_settings.marks.iter())
+                .map(|(line, _, _)| *line)
+                .min()
+                .unwrap_or(usize::MAX)
+        };
+
+        self.settings = new_settings.clone();
+        self.change_line(line);
+    }
+
+    fn change_line(&mut self, line: usize) {
+        self.current_line = self.current_line.min(line);
+        self.contexts.truncate(self.current_line + 1);
+    }
+
+    fn highlight_line(&mut self, line: &str) -> Self::Iterator<'_> {
+        let (context, mut depth) = self
+            .contexts
+            .get(self.current_line)
+            .copied()
+            .unwrap_or_default();
+
+        let (tokens, context) = Syntax::scan(line, context);
+
+        let mark = |start: usize| {
+            self.settings
+                .marks
+                .iter()
+                .find(|(line, range, _)| *line == self.current_line && range.start == start)
+                .map(|(_, _, mark)| *mark)
+        };
+
+        let mut highlights = Vec::new();
+        let mut head = false;
+
+        for token in tokens {
+            let text = &line[token.start..token.end];
+            let range = token.start..token.end;
+
+            match token.kind {
+                TokenKind::Atom => match Syntax::atom(text) {
+                    Atom::Keyword => highlights.push((range, Highlight::Keyword)),
+                    Atom::Number => highlights.push((range, Highlight::Number)),
+                    Atom::Qualified(ns) => {
+                        highlights.push((token.start..token.start + ns, Highlight::Namespace));
+
+                        if head && SPECIAL_FORMS.contains(&&text[ns..]) {
+                            highlights.push((token.start + ns..token.end, Highlight::Special));
+                        }
+                    }
+                    Atom::Symbol => {
+                        if head && SPECIAL_FORMS.contains(&text) {
+                            highlights.push((range, Highlight::Special))
+                        }
+                    }
+                },
+                TokenKind::Char => highlights.push((range, Highlight::Char)),
+                TokenKind::Comment => highlights.push((range, Highlight::Comment)),
+                TokenKind::Open | TokenKind::Close => {
+                    let unmatched = token.kind == TokenKind::Close && depth == 0;
+
+                    if token.kind == TokenKind::Close {
+                        depth = depth.saturating_sub(1)
+                    }
+
+                    let highlight = match mark(token.start) {
+                        Some(Mark::Matched) => Some(Highlight::Matched),
+                        Some(Mark::Unmatched) => Some(Highlight::Unmatched),
+                        None if unmatched => Some(Highlight::Unmatched),
+                        None => self.settings.rainbow.then_some(Highlight::Delimiter(depth)),
+                    };
+
+                    if let Some(highlight) = highlight {
+                        highlights.push((range, highlight))
+                    }
+
+                    if token.kind == TokenKind::Open {
+                        depth += 1
+                    }
+                }
+                TokenKind::Prefix => highlights.push((range, Highlight::Quote)),
+                TokenKind::String => highlights.push((range, Highlight::String)),
+            }
+
+            head = token.kind == TokenKind::Open;
+        }
+
+        self.contexts.truncate(self.current_line + 1);
+        self.contexts.push((context, depth));
+        self.current_line += 1;
+
+        highlights.into_iter()
+    }
+
+    fn current_line(&self) -> usize {
+        self.current_line
+    }
+}
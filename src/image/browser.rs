@@ -1,21 +1,193 @@
 //  SPDX-FileCopyrightText: Copyright 2024 James M. Putnam (putnamjm.design@gmail.com)
 //  SPDX-License-Identifier: MIT
 #![allow(dead_code)]
-#![allow(unused)]
 use {
     crate::{
-        config::Config,
-        image::{env_::Env_, repl::Repl},
+        image::mu::Mu,
+        syntax::{Sexp, Syntax},
     },
-    mu::{Mu, Tag},
+    mu::Tag,
 };
 
 pub trait Browser {
     fn browse(&self, _: Tag) -> String;
+    fn namespace(&self, _: &str) -> String;
+    fn symbols(&self, _: &str) -> Vec<Symbol>;
+    fn symbol_names(&self, _: &str) -> Vec<String>;
 }
 
+// a browsable symbol, rendered in the environment that interned it
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub bound: bool,
+    pub function: bool,
+    pub value: String,
+}
+
+const VALUE_WIDTH: usize = 64;
+
 impl Browser for Mu {
-    fn browse(&self, _form: Tag) -> String {
-        String::new()
+    // browse a namespace designated by form, nil browses every namespace
+    // this environment has loaded
+    fn browse(&self, form: Tag) -> String {
+        if self.is_nil(form) {
+            self.env
+                .namespaces
+                .iter()
+                .map(|ns| self.namespace(ns))
+                .collect::<Vec<String>>()
+                .join("\n")
+        } else {
+            self.namespace(&self.write(form, false))
+        }
+    }
+
+    fn namespace(&self, ns: &str) -> String {
+        if !self.eval_bool(format!("(mu:find-namespace \"{ns}\")")) {
+            return format!(";;; namespace {ns} not found\n");
+        }
+
+        let symbols = self.symbols(ns);
+        let width = symbols
+            .iter()
+            .map(|symbol| symbol.name.len())
+            .max()
+            .unwrap_or(0);
+
+        let listing = symbols
+            .iter()
+            .map(|symbol| {
+                let value = if symbol.function {
+                    "function".into()
+                } else if symbol.bound {
+                    let mut value = symbol.value.replace('\n', " ");
+
+                    if value.chars().count() > VALUE_WIDTH {
+                        value = value.chars().take(VALUE_WIDTH).collect::<String>() + "...";
+                    }
+
+                    value
+                } else {
+                    "unbound".into()
+                };
+
+                format!("{ns}:{:width$}  {value}\n", symbol.name)
+            })
+            .collect::<String>();
+
+        format!(";;; namespace {ns}, {} symbols\n{listing}", symbols.len())
+    }
+
+    // one eval for the whole namespace, each symbol written as
+    // ("name" bound type value)
+    fn symbols(&self, ns: &str) -> Vec<Symbol> {
+        let symbol = "(mu:car (mu:cdr state))";
+        let value = format!("(:if (mu:boundp {symbol}) (mu:symbol-value {symbol}) ())");
+        let entry = format!(
+            "(mu:cons (mu:symbol-name {symbol}) (mu:cons (mu:boundp {symbol}) \
+             (mu:cons (mu:type-of {value}) (mu:cons {value} ()))))"
+        );
+
+        let Some(listing) = self.walk_symbols(ns, &entry) else {
+            return Vec::new();
+        };
+
+        let sexps = Syntax::sexps(&listing);
+        let mut symbols = Self::entries(&sexps)
+            .filter_map(|entry| {
+                let items = Self::children(&sexps, entry).collect::<Vec<usize>>();
+                let close = sexps[entry].close.as_ref()?.start;
+
+                match items[..] {
+                    [name, bound, type_, value, ..] => {
+                        let bound = &listing[sexps[bound].start..sexps[bound].end] != ":nil";
+
+                        Some(Symbol {
+                            name: Self::unquote(&listing[sexps[name].start..sexps[name].end]),
+                            bound,
+                            function: &listing[sexps[type_].start..sexps[type_].end] == ":func",
+                            value: match bound {
+                                true => listing[sexps[value].start..close].trim().to_string(),
+                                false => String::new(),
+                            },
+                        })
+                    }
+                    _ => None,
+                }
+            })
+            .collect::<Vec<Symbol>>();
+
+        symbols.sort_by(|a, b| a.name.cmp(&b.name));
+        symbols
+    }
+
+    // just the names, without writing every value
+    fn symbol_names(&self, ns: &str) -> Vec<String> {
+        let Some(listing) = self.walk_symbols(ns, "(mu:symbol-name (mu:car (mu:cdr state)))")
+        else {
+            return Vec::new();
+        };
+
+        let sexps = Syntax::sexps(&listing);
+
+        Self::entries(&sexps)
+            .map(|name| Self::unquote(&listing[sexps[name].start..sexps[name].end]))
+            .collect()
+    }
+}
+
+impl Mu {
+    // walk a namespace's symbol list with car and cdr in a single eval,
+    // writing entry for each symbol. state is the entries so far consed
+    // onto the symbols left, mu:fix stops when it comes back unchanged.
+    fn walk_symbols(&self, ns: &str, entry: &str) -> Option<String> {
+        let symbols = format!("(mu:namespace-symbols (mu:find-namespace \"{ns}\"))");
+
+        self.eval_write(
+            format!(
+                "(mu:car (mu:fix (:lambda (state) (:if (mu:cdr state) \
+                 (mu:cons (mu:cons {entry} (mu:car state)) (mu:cdr (mu:cdr state))) \
+                 state)) (mu:cons () {symbols})))"
+            ),
+            true,
+        )
+    }
+
+    // the elements of a written list
+    fn entries(sexps: &[Sexp]) -> impl Iterator<Item = usize> + '_ {
+        let list = sexps
+            .iter()
+            .position(|sexp| sexp.parent.is_none() && sexp.open.is_some());
+
+        list.into_iter()
+            .flat_map(move |list| Self::children(sexps, list))
+    }
+
+    fn children(sexps: &[Sexp], parent: usize) -> impl Iterator<Item = usize> + '_ {
+        sexps
+            .iter()
+            .enumerate()
+            .filter(move |(_, sexp)| sexp.parent == Some(parent))
+            .map(|(nth, _)| nth)
+    }
+
+    // a written string without its quotes and escapes
+    fn unquote(written: &str) -> String {
+        let mut chars = written
+            .strip_prefix('"')
+            .and_then(|text| text.strip_suffix('"'))
+            .unwrap_or(written)
+            .chars();
+        let mut text = String::new();
+
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\\' => text.extend(chars.next()),
+                _ => text.push(ch),
+            }
+        }
+
+        text
     }
 }
@@ -0,0 +1,132 @@
+//  SPDX-FileCopyrightText: Copyright 2025 James M. Putnam (putnamjm.design@gmail.com)
+//  SPDX-License-Identifier: MIT
+#![allow(dead_code)]
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Out,
+    Err,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Output {
+    pub stream: Stream,
+    pub text: String,
+}
+
+// the environments' workers drain mu's string streams into the console
+// after each job, the gui takes it from there. shared by every worker.
+#[derive(Debug, Clone, Default)]
+pub struct Console {
+    output: Arc<Mutex<Vec<Output>>>,
+}
+
+impl Console {
+    // bytes of output kept, older output is dropped
+    pub const LIMIT: usize = 64 * 1024;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&self, outputs: Vec<Output>) {
+        let mut pending = self.output.lock().unwrap();
+
+        for output in outputs {
+            Self::append(&mut pending, output, Self::LIMIT)
+        }
+    }
+
+    pub fn drain(&self) -> Vec<Output> {
+        std::mem::take(&mut *self.output.lock().unwrap())
+    }
+
+    // add output to the end of outputs, merged with output from the same
+    // stream, and drop bytes from the front past limit
+    pub fn append(outputs: &mut Vec<Output>, output: Output, limit: usize) {
+        if output.text.is_empty() {
+            return;
+        }
+
+        match outputs.last_mut() {
+            Some(last) if last.stream == output.stream => last.text.push_str(&output.text),
+            _ => outputs.push(output),
+        }
+
+        let mut excess = outputs
+            .iter()
+            .map(|output| output.text.len())
+            .sum::<usize>()
+            .saturating_sub(limit);
+
+        while excess > 0 {
+            let first = &mut outputs[0];
+
+            if first.text.len() <= excess {
+                excess -= first.text.len();
+                outputs.remove(0);
+            } else {
+                let cut = (excess..first.text.len())
+                    .find(|cut| first.text.is_char_boundary(*cut))
+                    .unwrap_or(first.text.len());
+
+                first.text.drain(..cut);
+                excess = 0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn out(stream: Stream, text: &str) -> Output {
+        Output {
+            stream,
+            text: text.into(),
+        }
+    }
+
+    #[test]
+    fn merges_streams() {
+        let mut outputs = Vec::new();
+
+        for output in [
+            out(Stream::Out, "a"),
+            out(Stream::Out, "b"),
+            out(Stream::Err, ""),
+            out(Stream::Err, "c"),
+            out(Stream::Out, "d"),
+        ] {
+            Console::append(&mut outputs, output, 100)
+        }
+
+        assert_eq!(
+            outputs,
+            [
+                out(Stream::Out, "ab"),
+                out(Stream::Err, "c"),
+                out(Stream::Out, "d")
+            ]
+        );
+    }
+
+    #[test]
+    fn limits_bytes() {
+        let mut outputs = Vec::new();
+
+        Console::append(&mut outputs, out(Stream::Out, "0123456789"), 8);
+        assert_eq!(outputs, [out(Stream::Out, "23456789")]);
+
+        Console::append(&mut outputs, out(Stream::Err, "abcdefghij"), 8);
+        assert_eq!(outputs, [out(Stream::Err, "cdefghij")]);
+
+        // cut on a character boundary
+        let mut outputs = Vec::new();
+
+        Console::append(&mut outputs, out(Stream::Out, "ééé"), 5);
+        assert_eq!(outputs, [out(Stream::Out, "éé")]);
+    }
+}
@@ -3,17 +3,21 @@
 #![allow(dead_code)]
 
 use {
-    crate::image::{mu::Mu, repl::Repl},
+    crate::image::{
+        exception::{Exception, Phase},
+        mu::Mu,
+        repl::Repl,
+    },
     mu::{Mu as Mu_, Tag},
 };
 
 pub trait Core {
     fn version(&self) -> String;
-    fn load(&self, _: &str) -> std::result::Result<bool, String>;
-    fn eval_string(&self, _: String) -> std::result::Result<Tag, String>;
-    fn read(&self, _: String) -> std::result::Result<Tag, String>;
-    fn compile(&self, _: Tag) -> std::result::Result<Tag, String>;
-    fn eval(&self, _: Tag) -> std::result::Result<Tag, String>;
+    fn load(&self, _: &str) -> std::result::Result<bool, Exception>;
+    fn eval_string(&self, _: String) -> std::result::Result<Tag, Exception>;
+    fn read(&self, _: String) -> std::result::Result<Tag, Exception>;
+    fn compile(&self, _: Tag) -> std::result::Result<Tag, Exception>;
+    fn eval(&self, _: Tag) -> std::result::Result<Tag, Exception>;
     fn write(&self, _: Tag, _: bool) -> String;
     fn listener(&self);
 }
@@ -23,35 +27,35 @@ impl Core for Mu {
         Mu_::version().into()
     }
 
-    fn load(&self, path: &str) -> std::result::Result<bool, String> {
+    fn load(&self, path: &str) -> std::result::Result<bool, Exception> {
         match Mu_::load(self.env.env, path) {
             Ok(_) => Ok(true),
-            Err(ex) => Err(Mu_::exception_string(self.env.env, ex)),
+            Err(ex) => Err(Exception::new(self.env.env, Phase::Load, ex)),
         }
     }
 
-    fn eval_string(&self, form: String) -> std::result::Result<Tag, String> {
+    fn eval_string(&self, form: String) -> std::result::Result<Tag, Exception> {
         self.eval(self.compile(self.read(form)?)?)
     }
 
-    fn read(&self, form: String) -> std::result::Result<Tag, String> {
+    fn read(&self, form: String) -> std::result::Result<Tag, Exception> {
         match Mu_::read_str(self.env.env, &form) {
             Ok(tag) => Ok(tag),
-            Err(ex) => Err(Mu_::exception_string(self.env.env, ex)),
+            Err(ex) => Err(Exception::new(self.env.env, Phase::Read, ex)),
         }
     }
 
-    fn compile(&self, form: Tag) -> std::result::Result<Tag, String> {
+    fn compile(&self, form: Tag) -> std::result::Result<Tag, Exception> {
         match Mu_::compile(self.env.env, form) {
             Ok(tag) => Ok(tag),
-            Err(ex) => Err(Mu_::exception_string(self.env.env, ex)),
+            Err(ex) => Err(Exception::new(self.env.env, Phase::Compile, ex)),
         }
     }
 
-    fn eval(&self, form: Tag) -> std::result::Result<Tag, String> {
+    fn eval(&self, form: Tag) -> std::result::Result<Tag, Exception> {
         match Mu_::eval(self.env.env, form) {
             Ok(tag) => Ok(tag),
-            Err(ex) => Err(Mu_::exception_string(self.env.env, ex)),
+            Err(ex) => Err(Exception::new(self.env.env, Phase::Eval, ex)),
         }
     }
 
@@ -2,106 +2,303 @@
 //  SPDX-License-Identifier: MIT
 #![allow(dead_code)]
 use {
-    crate::config::{Config, ConfigOpt},
+    crate::{
+        config::Config,
+        image::{
+            console::{Output, Stream},
+            modules::Modules,
+        },
+    },
     mu::{Env, Mu},
+    std::{
+        fmt,
+        path::{Path, PathBuf},
+    },
 };
 
+// one thing startup tried to load, and where from or why not
+#[derive(Debug, Clone)]
+pub struct Load {
+    pub name: String,
+    pub result: Result<String, String>,
+}
+
+// what environment startup loaded and what it couldn't. a failed load
+// leaves the environment running without it.
+#[derive(Debug, Clone, Default)]
+pub struct Startup {
+    pub loads: Vec<Load>,
+}
+
 pub struct Env_ {
     pub env: Env,
     pub config: Config,
     pub ns: String,
+    pub namespaces: Vec<String>,
+    pub startup: Startup,
+    captured: bool,
+}
+
+// mu's standard streams, and where their output goes in the console
+const STREAMS: [(&str, Stream); 2] = [
+    ("*standard-output*", Stream::Out),
+    ("*error-output*", Stream::Err),
+];
+
+impl Startup {
+    fn record(&mut self, name: &str, result: Result<String, String>) -> bool {
+        let loaded = result.is_ok();
+
+        self.loads.push(Load {
+            name: name.into(),
+            result,
+        });
+
+        loaded
+    }
+
+    // a startup that didn't finish, its worker was abandoned
+    pub fn abandoned(why: String) -> Self {
+        let mut startup = Self::default();
+
+        startup.record("startup", Err(why));
+        startup
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &Load> {
+        self.loads.iter().filter(|load| load.result.is_err())
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        self.failures().next().is_some()
+    }
 }
 
 impl Env_ {
-    pub fn new(config: Config) -> Self {
-        let env = match config.map("config") {
-            Some(config) => Mu::make_env(
-                &Mu::config(Some(config))
-                    .expect("listener: can't allocate env with config {config:?}"),
-            ),
-            None => {
-                Mu::make_env(&Mu::config(None).expect("listener: can't to allocate default env"))
-            }
-        };
+    // a captured environment's standard streams are string streams,
+    // drained after each eval. otherwise they're the process's.
+    pub fn new(config: Config, capture: bool) -> Self {
+        let mut startup = Startup::default();
 
-        let ns = match config.map("namespace") {
-            Some(ns) => match ns.as_str() {
-                "mu" => "mu",
-                "core" => {
-                    Self::load_sys(env, "core.sys");
-                    "core"
-                }
-                "common" => {
-                    Self::load_sys(env, "core.sys");
-                    Self::load_sys(env, "common.fasl");
-                    "common"
-                }
-                "prelude" => {
-                    Self::load_sys(env, "core.sys");
-                    Self::load_sys(env, "prelude.fasl");
-                    "prelue"
-                }
-                _ => {
-                    eprintln!("listener: unrecognized namespace: {ns}",);
-                    std::process::exit(-1)
+        let env_config = match &config.env {
+            Some(options) => match Mu::config(Some(options.clone())) {
+                Some(env_config) => Some(env_config),
+                None => {
+                    startup.record(
+                        "config",
+                        Err(format!("mu rejected {options}, using the defaults")),
+                    );
+                    Mu::config(None)
                 }
             },
-            None => "mu",
+            None => Mu::config(None),
         };
 
-        match config.map_opt("modules") {
-            Some(modules) => match modules {
-                ConfigOpt::Array(vec) => {
-                    for module in vec {
-                        match module {
-                            ConfigOpt::String(str) => Self::load_sys(env, &str),
-                            _ => panic!(),
+        let env = Mu::make_env(&env_config.expect("listener: can't allocate default env"));
+
+        // before anything loads, startup output is the console's too
+        let captured = capture
+            && match Self::capture(env) {
+                Ok(()) => true,
+                Err(why) => {
+                    startup.record("console", Err(why));
+                    false
+                }
+            };
+
+        let mut namespaces = vec!["mu".to_string()];
+        let mut ns = "mu".to_string();
+
+        // a preset's files depend on the ones before them, so the first
+        // that doesn't load ends it and the namespace is what did
+        match config.presets.get(&config.namespace) {
+            Some(files) => {
+                let mut failed = None;
+
+                for file in files {
+                    let result = match &failed {
+                        Some(failed) => Err(format!("skipped, {failed} didn't load")),
+                        None => Self::load_sys(env, &config.lib_path, file),
+                    };
+
+                    if startup.record(file, result) {
+                        ns = Self::module_ns(file);
+
+                        if !namespaces.contains(&ns) {
+                            namespaces.push(ns.clone())
                         }
+                    } else if failed.is_none() {
+                        failed = Some(file.clone())
                     }
-                },
-                _ => (),
-            },
-            None => (),
+                }
+            }
+            None => {
+                startup.record(&config.namespace, Err("no such namespace preset".into()));
+            }
         }
 
-        match config.map("rc") {
-            Some(rc) => match Mu::load(env, rc.as_str()) {
-                Ok(bool_) => bool_,
-                Err(e) => {
-                    eprintln!(
-                        "core-cli: can't load rc file {rc}: {}",
-                        Mu::exception_string(env, e)
-                    );
-                    std::process::exit(-1)
-                }
-            },
-            None => false,
-        };
+        // modules load after what they require, a module whose
+        // requirement didn't load is skipped. what they require is read
+        // from their %sys-def without loading them.
+        let plan = Modules::plan(&config, &namespaces);
+        let mut failed = Vec::new();
+
+        for (module, why) in plan.problems {
+            startup.record(&module, Err(why));
+        }
+
+        for step in plan.order {
+            let module_ns = Self::module_ns(&step.module);
+            let result = match step.requires.iter().find(|ns| failed.contains(*ns)) {
+                Some(ns) => Err(format!("skipped, {ns} didn't load")),
+                None => Self::load_sys(env, &config.lib_path, &step.module),
+            };
+
+            if !startup.record(&step.module, result) {
+                failed.push(module_ns)
+            } else if !namespaces.contains(&module_ns) {
+                namespaces.push(module_ns)
+            }
+        }
+
+        if let Some(rc) = &config.rc {
+            let result = match Mu::load(env, rc.as_str()) {
+                Ok(true) => Ok(rc.clone()),
+                Ok(false) => Err(format!("{rc} didn't load")),
+                Err(e) => Err(format!("exception: {}", Mu::exception_string(env, e))),
+            };
+
+            startup.record(rc, result);
+        }
 
         Self {
             env,
             config,
-            ns: ns.into(),
+            ns,
+            namespaces,
+            startup,
+            captured,
+        }
+    }
+
+    fn capture(env: Env) -> Result<(), String> {
+        for (stream, _) in STREAMS {
+            Mu::eval_str(
+                env,
+                &format!(
+                    "(mu:intern (mu:find-namespace \"mu\") \"{stream}\" \
+                     (mu:open :string :output \"\" :t))"
+                ),
+            )
+            .map_err(|e| {
+                format!(
+                    "can't capture mu:{stream}: {}",
+                    Mu::exception_string(env, e)
+                )
+            })?;
+        }
+
+        Ok(())
+    }
+
+    // what's been written to the captured streams since the last drain
+    pub fn drain(&self) -> Vec<Output> {
+        if !self.captured {
+            return Vec::new();
+        }
+
+        STREAMS
+            .into_iter()
+            .filter_map(|(name, stream)| {
+                let text = Mu::eval_str(self.env, &format!("(mu:get-string mu:{name})")).ok()?;
+
+                Some(Output {
+                    stream,
+                    text: Mu::write_to_string(self.env, text, false),
+                })
+            })
+            .filter(|output| !output.text.is_empty())
+            .collect()
+    }
+
+    // modules are named for their namespace, less any directory and the
+    // file extension
+    pub fn module_ns(module: &str) -> String {
+        match Path::new(module).file_stem() {
+            Some(ns) => ns.to_string_lossy().into_owned(),
+            None => module.into(),
+        }
+    }
+
+    // the first directory on the lib path holding name, or every place
+    // that was tried
+    pub fn find_lib(lib_path: &[PathBuf], name: &str) -> Result<PathBuf, Vec<PathBuf>> {
+        let tried = if Path::new(name).is_absolute() {
+            vec![PathBuf::from(name)]
+        } else {
+            lib_path.iter().map(|dir| dir.join(name)).collect()
+        };
+
+        match tried.iter().find(|path| path.is_file()) {
+            Some(path) => Ok(path.clone()),
+            None => Err(tried),
         }
     }
 
-    pub fn load_sys(env: Env, name: &str) {
-        let sys = format!("/opt/mu/lib/{name}");
+    // load a system image or module from the lib path, the path it
+    // loaded from or why it didn't
+    pub fn load_sys(env: Env, lib_path: &[PathBuf], name: &str) -> Result<String, String> {
+        let sys = Self::find_lib(lib_path, name).map_err(|tried| {
+            format!(
+                "not found, tried {}",
+                tried
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )
+        })?;
+
+        let sys = sys.to_string_lossy().into_owned();
 
         match Mu::load(env, sys.as_str()) {
-            Ok(bool_) => {
-                if !bool_ {
-                    eprintln!("listener: can't load {name}");
-                    std::process::exit(-1)
-                }
-            }
-            Err(e) => {
-                eprintln!(
-                    "listener: exception while loading {name}: {}",
-                    Mu::exception_string(env, e)
-                );
-                std::process::exit(-1)
+            Ok(true) => Ok(sys),
+            Ok(false) => Err(format!("{sys} didn't load")),
+            Err(e) => Err(format!(
+                "exception while loading {sys}: {}",
+                Mu::exception_string(env, e)
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Startup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let failed = self.failures().count();
+
+        match failed {
+            0 => writeln!(f, "environment started")?,
+            _ => writeln!(
+                f,
+                "environment started degraded, {failed} of {} loads failed",
+                self.loads.len()
+            )?,
+        }
+
+        let width = self
+            .loads
+            .iter()
+            .map(|load| load.name.len())
+            .max()
+            .unwrap_or(0);
+
+        for load in &self.loads {
+            match &load.result {
+                Ok(path) => writeln!(f, "  {:width$}  loaded from {path}", load.name)?,
+                Err(why) => writeln!(f, "  {:width$}  failed: {why}", load.name)?,
             }
         }
+
+        Ok(())
     }
 }
@@ -0,0 +1,57 @@
+//  SPDX-FileCopyrightText: Copyright 2025 James M. Putnam (putnamjm.design@gmail.com)
+//  SPDX-License-Identifier: MIT
+#![allow(dead_code)]
+use {
+    mu::{Condition, Env, Exception as Exception_, Mu},
+    std::fmt,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Read,
+    Compile,
+    Eval,
+    Load,
+}
+
+// a mu exception, with its tags written out while the raising
+// environment is still at hand
+#[derive(Debug, Clone)]
+pub struct Exception {
+    pub phase: Phase,
+    pub condition: Condition,
+    pub source: String,
+    pub object: String,
+}
+
+impl Exception {
+    pub fn new(env: Env, phase: Phase, ex: Exception_) -> Self {
+        Self {
+            phase,
+            condition: ex.condition,
+            source: Mu::write_to_string(env, ex.source, true),
+            object: Mu::write_to_string(env, ex.object, true),
+        }
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Phase::Read => "reader",
+            Phase::Compile => "compiler",
+            Phase::Eval => "eval",
+            Phase::Load => "load",
+        })
+    }
+}
+
+impl fmt::Display for Exception {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} exception raised by {}, {:?} condition on {}",
+            self.phase, self.source, self.condition, self.object
+        )
+    }
+}
@@ -0,0 +1,63 @@
+//  SPDX-FileCopyrightText: Copyright 2025 James M. Putnam (putnamjm.design@gmail.com)
+//  SPDX-License-Identifier: MIT
+
+//! listener meta commands
+
+pub const HELP: &str = "\
+:load FILE       load FILE
+:ns [NAME]       show or change the listener's namespace
+:describe SYM    describe a symbol, qualified or in the listener's namespace
+:time FORM       evaluate FORM and report how long it took
+:apropos STR     list the symbols whose names contain STR
+:history         list the listener history
+:quit            leave the listener
+:help            list these commands
+
+the listener's namespace picks the reader forms are read and compiled
+with, mu's for mu and core's for the rest, and is where bare names
+complete and describe from. :ns doesn't change mu's own namespace, what
+a form interns goes where that reader puts it.
+
+anything else is read as lisp, keywords included.";
+
+// commands the listener handles itself, whatever namespaces are loaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Meta {
+    Apropos(String),
+    Describe(String),
+    Help,
+    History,
+    Load(String),
+    Ns(Option<String>),
+    Quit,
+    Time(String),
+}
+
+impl Meta {
+    // None if line isn't a meta command, so a keyword on its own still
+    // evaluates. a command missing its argument is an error.
+    pub fn parse(line: &str) -> Option<Result<Self, String>> {
+        let line = line.trim();
+        let (command, arg) = match line.split_once(char::is_whitespace) {
+            Some((command, arg)) => (command, arg.trim()),
+            None => (line, ""),
+        };
+
+        let needs_arg = |meta: fn(String) -> Self| match arg {
+            "" => Err(format!("{command} needs an argument")),
+            arg => Ok(meta(arg.into())),
+        };
+
+        Some(match command {
+            ":apropos" => needs_arg(Self::Apropos),
+            ":describe" => needs_arg(Self::Describe),
+            ":help" => Ok(Self::Help),
+            ":history" => Ok(Self::History),
+            ":load" => needs_arg(Self::Load),
+            ":ns" => Ok(Self::Ns((!arg.is_empty()).then(|| arg.into()))),
+            ":quit" => Ok(Self::Quit),
+            ":time" => needs_arg(Self::Time),
+            _ => return None,
+        })
+    }
+}
@@ -1,7 +1,12 @@
 //  SPDX-FileCopyrightText: Copyright 2024 James M. Putnam (putnamjm.design@gmail.com)
 //  SPDX-License-Identifier: MIT
 pub mod browser;
+pub mod console;
 pub mod core;
 pub mod env_;
+pub mod exception;
+pub mod meta;
+pub mod modules;
 pub mod mu;
 pub mod repl;
+pub mod worker;
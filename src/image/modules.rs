@@ -0,0 +1,741 @@
+//  SPDX-FileCopyrightText: Copyright 2025 James M. Putnam (putnamjm.design@gmail.com)
+//  SPDX-License-Identifier: MIT
+
+//! module system definitions and load order
+use {
+    crate::{
+        config::Config,
+        image::env_::Env_,
+        syntax::{Sexp, Syntax, Token, TokenKind},
+    },
+    std::{
+        cmp::Ordering,
+        collections::BTreeMap,
+        fmt, fs,
+        path::{Path, PathBuf},
+    },
+};
+
+// the parts of a module's %sys-def rebel reads from its file before
+// loading it, the alist the module defines it as, e.g.
+//
+//   ((version . "0.0.3") (requires "common" "deftype"))
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SysDef {
+    pub version: Option<String>,
+    pub requires: Vec<String>,
+}
+
+// dotted numbers, missing components are zeros
+#[derive(Debug, Clone)]
+pub struct Version(Vec<u64>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Caret,
+    Eq,
+    Ge,
+    Gt,
+    Le,
+    Lt,
+    Tilde,
+}
+
+// comparators separated by commas, all of which have to hold. a bare
+// version is an exact match, ^ and ~ are as cargo has them and * is
+// anything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraint(Vec<(Op, Version)>);
+
+// a module to load, after the namespaces it requires
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub module: String,
+    pub requires: Vec<String>,
+}
+
+// the load order, and the modules left out of it and why
+#[derive(Debug, Clone, Default)]
+pub struct Plan {
+    pub order: Vec<Step>,
+    pub problems: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Visiting,
+    Done(bool),
+}
+
+// the walk over the configured modules' requirements
+struct Planner<'a> {
+    config: &'a Config,
+    loaded: &'a [String],
+    states: BTreeMap<String, State>,
+    stack: Vec<String>,
+    plan: Plan,
+}
+
+// a text's s-expressions and the tokens they start with
+struct Forms<'a> {
+    text: &'a str,
+    sexps: Vec<Sexp>,
+    tokens: Vec<Token>,
+}
+
+pub struct Modules;
+
+impl Version {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        text.trim()
+            .split('.')
+            .map(|part| part.parse::<u64>())
+            .collect::<Result<Vec<u64>, _>>()
+            .map(Self)
+            .map_err(|_| format!("{text} isn't a dotted version number"))
+    }
+
+    fn part(&self, nth: usize) -> u64 {
+        self.0.get(nth).copied().unwrap_or(0)
+    }
+
+    // the first version past the ones ^ or ~ accept
+    fn bump(&self, op: Op) -> Self {
+        let nth = match op {
+            Op::Caret => self
+                .0
+                .iter()
+                .position(|part| *part != 0)
+                .unwrap_or(self.0.len() - 1),
+            _ => match self.0.len() {
+                1 => 0,
+                _ => 1,
+            },
+        };
+
+        let mut parts = self.0[..=nth].to_vec();
+
+        parts[nth] += 1;
+        Self(parts)
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (0..self.0.len().max(other.0.len()))
+            .map(|nth| self.part(nth).cmp(&other.part(nth)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Constraint {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        if text.trim() == "*" {
+            return Ok(Self(Vec::new()));
+        }
+
+        text.split(',')
+            .map(|comparator| {
+                let comparator = comparator.trim();
+                let (op, version) = [
+                    (">=", Op::Ge),
+                    ("<=", Op::Le),
+                    (">", Op::Gt),
+                    ("<", Op::Lt),
+                    ("=", Op::Eq),
+                    ("^", Op::Caret),
+                    ("~", Op::Tilde),
+                ]
+                .into_iter()
+                .find_map(|(prefix, op)| comparator.strip_prefix(prefix).map(|rest| (op, rest)))
+                .unwrap_or((Op::Eq, comparator));
+
+                Ok((op, Version::parse(version)?))
+            })
+            .collect::<Result<Vec<(Op, Version)>, String>>()
+            .map(Self)
+    }
+
+    pub fn matches(&self, version: &Version) -> bool {
+        self.0.iter().all(|(op, bound)| match op {
+            Op::Eq => version == bound,
+            Op::Ge => version >= bound,
+            Op::Gt => version > bound,
+            Op::Le => version <= bound,
+            Op::Lt => version < bound,
+            Op::Caret | Op::Tilde => version >= bound && *version < bound.bump(*op),
+        })
+    }
+}
+
+impl Modules {
+    // what mu names compiled modules and system images
+    const EXTENSIONS: &[&str] = &["fasl", "sys"];
+
+    // a module's %sys-def read from its file without loading it, none if
+    // it doesn't define one. a system image isn't text and has none to
+    // read.
+    pub fn read_sys_def(path: &Path) -> Result<Option<SysDef>, String> {
+        let bytes = fs::read(path).map_err(|err| format!("can't read: {err}"))?;
+
+        let Ok(text) = String::from_utf8(bytes) else {
+            return Ok(None);
+        };
+
+        Self::sys_def_form(&text)
+            .map(Self::parse_sys_def)
+            .transpose()
+    }
+
+    // the value of the first top-level form naming %sys-def, whatever
+    // form defines it, unquoted
+    fn sys_def_form(text: &str) -> Option<&str> {
+        let forms = Forms::new(text);
+        let unquote = |nth: usize| match forms
+            .children(nth)
+            .next()
+            .and_then(|first| forms.atom(first))
+            .as_deref()
+        {
+            Some(":quote" | "quote") => forms.children(nth).nth(1).unwrap_or(nth),
+            _ => nth,
+        };
+
+        let value = (0..forms.sexps.len())
+            .filter(|nth| forms.sexps[*nth].parent.is_none() && forms.sexps[*nth].open.is_some())
+            .find_map(|form| {
+                let children = forms.children(form).collect::<Vec<usize>>();
+                let value = *children.last()?;
+                let names = children.iter().any(|nth| {
+                    forms
+                        .atom(*nth)
+                        .is_some_and(|atom| atom.ends_with("%sys-def"))
+                });
+
+                (names && forms.atom(value).is_none()).then(|| unquote(value))
+            })?;
+
+        Some(&text[forms.sexps[value].start..forms.sexps[value].end])
+    }
+
+    // an alist of symbol or keyword keys, string values are unquoted and a
+    // dotted list's tail is taken as its values
+    pub fn parse_sys_def(text: &str) -> Result<SysDef, String> {
+        let forms = Forms::new(text);
+
+        let Some(alist) = forms
+            .sexps
+            .iter()
+            .position(|sexp| sexp.parent.is_none() && sexp.open.is_some() && sexp.close.is_some())
+        else {
+            return Err("expected the %sys-def alist".into());
+        };
+
+        let mut sys_def = SysDef::default();
+
+        for entry in forms.children(alist) {
+            let mut items = forms
+                .children(entry)
+                .filter(|nth| forms.atom(*nth).as_deref() != Some("."));
+            let Some(key) = items.next().and_then(|nth| forms.atom(nth)) else {
+                continue;
+            };
+
+            let values = items
+                .flat_map(|nth| match forms.sexps[nth].open {
+                    Some(_) => forms
+                        .children(nth)
+                        .filter_map(|nth| forms.atom(nth))
+                        .collect::<Vec<String>>(),
+                    None => forms.atom(nth).into_iter().collect(),
+                })
+                .collect::<Vec<String>>();
+
+            match key.trim_start_matches(':') {
+                "version" => sys_def.version = values.into_iter().next(),
+                "requires" => sys_def.requires = values,
+                _ => (),
+            }
+        }
+
+        Ok(sys_def)
+    }
+
+    // the configured modules and whatever they require, each after its
+    // requirements. namespaces already loaded satisfy a requirement,
+    // anything else is found on the lib path.
+    pub fn plan(config: &Config, loaded: &[String]) -> Plan {
+        let mut planner = Planner {
+            config,
+            loaded,
+            states: BTreeMap::new(),
+            stack: Vec::new(),
+            plan: Plan::default(),
+        };
+
+        for module in &config.modules {
+            planner.visit(module);
+        }
+
+        planner.plan
+    }
+}
+
+impl Planner<'_> {
+    fn visit(&mut self, name: &str) -> bool {
+        let ns = Env_::module_ns(name);
+
+        match self.states.get(&ns) {
+            Some(State::Done(ok)) => return *ok,
+            Some(State::Visiting) => {
+                let cycle = self
+                    .stack
+                    .iter()
+                    .skip_while(|visiting| **visiting != ns)
+                    .chain([&ns])
+                    .cloned()
+                    .collect::<Vec<String>>();
+
+                self.plan
+                    .problem(&ns, format!("dependency cycle {}", cycle.join(" -> ")));
+                return false;
+            }
+            None => (),
+        }
+
+        // a requirement names a namespace, the config may name its file
+        let module = self
+            .config
+            .modules
+            .iter()
+            .find(|module| Env_::module_ns(module) == ns)
+            .map_or(name, String::as_str);
+
+        self.states.insert(ns.clone(), State::Visiting);
+        self.stack.push(ns.clone());
+
+        let ok = self.check(module, &ns);
+
+        self.stack.pop();
+        self.states.insert(ns, State::Done(ok));
+
+        ok
+    }
+
+    fn check(&mut self, module: &str, ns: &str) -> bool {
+        let Some((module, path)) = self.find(module) else {
+            self.plan.problem(ns, "missing, not on the lib path".into());
+            return false;
+        };
+
+        // a module without a %sys-def has no requirements and no version
+        let sys_def = match Modules::read_sys_def(&path) {
+            Ok(sys_def) => sys_def.unwrap_or_default(),
+            Err(why) => {
+                self.plan.problem(ns, format!("{}: {why}", path.display()));
+                return false;
+            }
+        };
+
+        if let Some(constraint) = self.config.module_versions.get(ns) {
+            let why = match &sys_def.version {
+                None => Some(format!(
+                    "no version in its system definition to check against {constraint}"
+                )),
+                Some(version) => match Version::parse(version) {
+                    Ok(parsed) if constraint.matches(&parsed) => None,
+                    Ok(_) => Some(format!("version {version} doesn't satisfy {constraint}")),
+                    Err(why) => Some(why),
+                },
+            };
+
+            if let Some(why) = why {
+                self.plan.problem(ns, why);
+                return false;
+            }
+        }
+
+        let mut requires = Vec::new();
+
+        for require in &sys_def.requires {
+            let require_ns = Env_::module_ns(require);
+
+            if self.loaded.contains(&require_ns) {
+                continue;
+            }
+
+            if !self.visit(require) {
+                self.plan
+                    .problem(ns, format!("requires {require_ns}, which can't be loaded"));
+                return false;
+            }
+
+            requires.push(require_ns)
+        }
+
+        self.plan.order.push(Step { module, requires });
+
+        true
+    }
+
+    // a module's file is named as given, or for a bare namespace with one
+    // of mu's module extensions, the first of them on the lib path
+    fn find(&self, module: &str) -> Option<(String, PathBuf)> {
+        let mut candidates = vec![module.to_string()];
+
+        if Path::new(module).extension().is_none() {
+            candidates.extend(
+                Modules::EXTENSIONS
+                    .iter()
+                    .map(|extension| format!("{module}.{extension}")),
+            )
+        }
+
+        candidates.into_iter().find_map(|module| {
+            Env_::find_lib(&self.config.lib_path, &module)
+                .ok()
+                .map(|path| (module, path))
+        })
+    }
+}
+
+impl<'a> Forms<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            sexps: Syntax::sexps(text),
+            tokens: Syntax::tokens(text),
+        }
+    }
+
+    fn children(&self, parent: usize) -> impl Iterator<Item = usize> + '_ {
+        self.sexps
+            .iter()
+            .enumerate()
+            .filter(move |(_, sexp)| sexp.parent == Some(parent))
+            .map(|(nth, _)| nth)
+    }
+
+    // a string's contents or an atom's text
+    fn atom(&self, nth: usize) -> Option<String> {
+        let sexp = &self.sexps[nth];
+        let token = self.tokens.iter().find(|token| token.start == sexp.start);
+
+        match token.map(|token| token.kind) {
+            Some(TokenKind::String) => Some(self.text[sexp.start + 1..sexp.end - 1].to_string()),
+            Some(TokenKind::Atom) => Some(self.text[sexp.start..sexp.end].to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl Plan {
+    // the first reason a module can't load is the one reported
+    fn problem(&mut self, ns: &str, why: String) {
+        if !self.problems.iter().any(|(module, _)| module == ns) {
+            self.problems.push((ns.into(), why))
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts = self
+            .0
+            .iter()
+            .map(|part| part.to_string())
+            .collect::<Vec<String>>();
+
+        f.write_str(&parts.join("."))
+    }
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return f.write_str("*");
+        }
+
+        let comparators = self
+            .0
+            .iter()
+            .map(|(op, version)| {
+                let op = match op {
+                    Op::Caret => "^",
+                    Op::Eq => "=",
+                    Op::Ge => ">=",
+                    Op::Gt => ">",
+                    Op::Le => "<=",
+                    Op::Lt => "<",
+                    Op::Tilde => "~",
+                };
+
+                format!("{op}{version}")
+            })
+            .collect::<Vec<String>>();
+
+        f.write_str(&comparators.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::temp_dir::TempDir, std::fs};
+
+    fn matches(constraint: &str, version: &str) -> bool {
+        Constraint::parse(constraint)
+            .unwrap()
+            .matches(&Version::parse(version).unwrap())
+    }
+
+    // a lib directory holding a module for each system definition, by
+    // namespace
+    fn plan(
+        name: &str,
+        modules: &[&str],
+        versions: &[(&str, &str)],
+        sys_defs: &[(&str, &str)],
+    ) -> Plan {
+        let lib = TempDir::new(&format!("modules-{name}"));
+
+        for (ns, sys_def) in sys_defs {
+            lib.file(
+                &format!("{ns}.fasl"),
+                &format!("(mu:intern (mu:find-namespace \"{ns}\") \"%sys-def\" '{sys_def})\n"),
+            );
+        }
+
+        let mut config = Config::default();
+
+        config.lib_path = vec![lib.path().into()];
+        config.modules = modules.iter().map(|module| module.to_string()).collect();
+        config.module_versions = versions
+            .iter()
+            .map(|(ns, constraint)| (ns.to_string(), Constraint::parse(constraint).unwrap()))
+            .collect();
+
+        Modules::plan(&config, &["mu".into(), "core".into()])
+    }
+
+    fn order(plan: &Plan) -> Vec<&str> {
+        plan.order.iter().map(|step| step.module.as_str()).collect()
+    }
+
+    #[test]
+    fn constraints() {
+        assert!(matches("*", "3.1"));
+        assert!(matches("1.2", "1.2.0"));
+        assert!(!matches("=1.2", "1.2.1"));
+        assert!(matches(">=1.2, <2", "1.9.9"));
+        assert!(!matches(">=1.2, <2", "2.0"));
+        assert!(matches(">1", "1.0.1"));
+        assert!(matches("<=1.2", "1.2"));
+
+        assert!(matches("^1.2.3", "1.9"));
+        assert!(!matches("^1.2.3", "2.0.0"));
+        assert!(!matches("^1.2.3", "1.2.2"));
+        assert!(matches("^0.2.3", "0.2.9"));
+        assert!(!matches("^0.2.3", "0.3.0"));
+        assert!(matches("^0.0.3", "0.0.3"));
+        assert!(!matches("^0.0.3", "0.0.4"));
+        assert!(!matches("^0", "1.0"));
+
+        assert!(matches("~1.2.3", "1.2.9"));
+        assert!(!matches("~1.2.3", "1.3"));
+        assert!(matches("~1", "1.9"));
+        assert!(!matches("~1", "2"));
+        assert!(matches("~0.2", "0.2.5"));
+        assert!(!matches("~0.2", "0.3"));
+
+        assert!(Constraint::parse("^1.x").is_err());
+        assert!(Constraint::parse(">=1.2,").is_err());
+        assert_eq!(
+            Constraint::parse(" >= 1.2 , ~0.3").unwrap().to_string(),
+            ">=1.2, ~0.3"
+        );
+    }
+
+    #[test]
+    fn sys_defs() {
+        assert_eq!(
+            Modules::parse_sys_def(r#"((version . "0.0.3") (requires "common" "deftype"))"#),
+            Ok(SysDef {
+                version: Some("0.0.3".into()),
+                requires: vec!["common".into(), "deftype".into()],
+            })
+        );
+        assert_eq!(
+            Modules::parse_sys_def(r#"((:requires . ("core")) (:name . "x"))"#),
+            Ok(SysDef {
+                version: None,
+                requires: vec!["core".into()],
+            })
+        );
+        assert!(Modules::parse_sys_def("version").is_err());
+    }
+
+    // whatever form defines it, without loading the module
+    #[test]
+    fn read_sys_defs() {
+        let lib = TempDir::new("modules-read");
+        let read = |name: &str, text: &str| Modules::read_sys_def(&lib.file(name, text));
+        let version = |version: &str| {
+            Ok(Some(SysDef {
+                version: Some(version.into()),
+                requires: Vec::new(),
+            }))
+        };
+
+        assert_eq!(
+            read(
+                "a.fasl",
+                "; a\n(mu:make-namespace \"a\")\n(core:defconst a:%sys-def '((version . \"1.0\")))"
+            ),
+            version("1.0")
+        );
+        assert_eq!(
+            read(
+                "b.fasl",
+                "(mu:intern (mu:find-namespace \"b\") \"%sys-def\" (:quote ((version . \"2\"))))"
+            ),
+            version("2")
+        );
+        assert_eq!(read("c.fasl", "(mu:make-namespace \"c\")"), Ok(None));
+        assert_eq!(
+            Modules::read_sys_def(&lib.path().join("nowhere.fasl")).map_err(|_| ()),
+            Err(())
+        );
+
+        fs::write(lib.path().join("e.sys"), [0xff, 0xfe, 0]).unwrap();
+        assert_eq!(Modules::read_sys_def(&lib.path().join("e.sys")), Ok(None));
+    }
+
+    #[test]
+    fn load_order() {
+        let plan = plan(
+            "order",
+            &["a", "d.fasl"],
+            &[],
+            &[
+                ("a", r#"((requires "b" "core"))"#),
+                ("b", r#"((requires "c"))"#),
+                ("c", "()"),
+                ("d", r#"((requires "c" "a"))"#),
+            ],
+        );
+
+        assert_eq!(order(&plan), ["c.fasl", "b.fasl", "a.fasl", "d.fasl"]);
+        assert_eq!(plan.order[2].requires, ["b"]);
+        assert_eq!(plan.order[3].requires, ["c", "a"]);
+        assert!(plan.problems.is_empty());
+    }
+
+    // as etc/rebel names deftype, a file without an extension
+    #[test]
+    fn bare_names() {
+        let lib = TempDir::new("modules-bare");
+
+        lib.file(
+            "deftype",
+            "(core:defconst deftype:%sys-def '((requires \"common\")))",
+        );
+        lib.file("common.fasl", "");
+
+        let mut config = Config::default();
+
+        config.lib_path = vec![lib.path().into()];
+        config.modules = vec!["deftype".into()];
+
+        let plan = Modules::plan(&config, &["mu".into(), "core".into()]);
+
+        assert_eq!(order(&plan), ["common.fasl", "deftype"]);
+        assert!(plan.problems.is_empty());
+    }
+
+    #[test]
+    fn cycle() {
+        let plan = plan(
+            "cycle",
+            &["a"],
+            &[],
+            &[("a", r#"((requires "b"))"#), ("b", r#"((requires "a"))"#)],
+        );
+
+        assert!(plan.order.is_empty());
+        assert_eq!(
+            plan.problems,
+            [
+                ("a".into(), "dependency cycle a -> b -> a".into()),
+                ("b".into(), "requires a, which can't be loaded".into()),
+            ]
+        );
+    }
+
+    // a module that can't load takes the modules requiring it with it
+    #[test]
+    fn missing() {
+        let plan = plan(
+            "missing",
+            &["c", "b", "e"],
+            &[],
+            &[
+                ("a", r#"((requires "x"))"#),
+                ("b", r#"((requires "a"))"#),
+                ("c", "()"),
+                ("e", r#"((requires "c"))"#),
+            ],
+        );
+
+        assert_eq!(order(&plan), ["c.fasl", "e.fasl"]);
+        assert_eq!(
+            plan.problems,
+            [
+                ("x".into(), "missing, not on the lib path".into()),
+                ("a".into(), "requires x, which can't be loaded".into()),
+                ("b".into(), "requires a, which can't be loaded".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn versions() {
+        let plan = plan(
+            "versions",
+            &["a", "c"],
+            &[("b", "^0.2"), ("c", ">=1")],
+            &[
+                ("a", r#"((requires "b"))"#),
+                ("b", r#"((version . "0.3.1"))"#),
+                ("c", "()"),
+            ],
+        );
+
+        assert!(plan.order.is_empty());
+        assert_eq!(
+            plan.problems,
+            [
+                ("b".into(), "version 0.3.1 doesn't satisfy ^0.2".into()),
+                ("a".into(), "requires b, which can't be loaded".into()),
+                (
+                    "c".into(),
+                    "no version in its system definition to check against >=1".into()
+                ),
+            ]
+        );
+    }
+}
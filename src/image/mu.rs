@@ -11,19 +11,37 @@ static GLOBAL: Jemalloc = Jemalloc;
 
 use {
     crate::{
-        config::{Config, ConfigOpt},
-        image::{env_::Env_, repl::Repl},
+        config::Config,
+        image::{
+            env_::Env_,
+            exception::{Exception, Phase},
+            repl::Repl,
+        },
+        syntax::Syntax,
     },
-    mu::Tag,
+    mu::{Condition, Tag},
 };
 
 pub struct Mu {
     pub env: Env_,
 }
 
+#[derive(Debug, Clone)]
+pub struct FormResult {
+    pub form: String,
+    pub value: std::result::Result<String, Exception>,
+}
+
 impl Mu {
     pub fn new(config: &Config) -> Self {
-        let env = Env_::new(config.clone());
+        let env = Env_::new(config.clone(), false);
+
+        Self { env }
+    }
+
+    // an environment whose standard streams are drained to the console
+    pub fn with_console(config: &Config) -> Self {
+        let env = Env_::new(config.clone(), true);
 
         Self { env }
     }
@@ -32,43 +50,212 @@ impl Mu {
         mu::Mu::version().into()
     }
 
-    pub fn map_config(&self, name: &str) -> Option<String> {
-        self.env.config.map(name)
+    // false if mu didn't load the file, without raising anything
+    pub fn load(&self, path: &str) -> std::result::Result<bool, Exception> {
+        match mu::Mu::load(self.env.env, path) {
+            Ok(loaded) => Ok(loaded),
+            Err(ex) => Err(Exception::new(self.env.env, Phase::Load, ex)),
+        }
     }
 
-    pub fn map_config_opt(&self, name: &str) -> Option<ConfigOpt> {
-        self.env.config.map_opt(name)
+    pub fn eval_string(&self, form: String) -> std::result::Result<Tag, Exception> {
+        self.eval(self.compile(self.read(form)?)?)
     }
 
-    pub fn load(&self, path: &str) -> std::result::Result<bool, String> {
-        match mu::Mu::load(self.env.env, path) {
-            Ok(_) => Ok(true),
-            Err(ex) => Err(mu::Mu::exception_string(self.env.env, ex)),
+    // the reader a namespace's forms are read and compiled with. mu reads
+    // with the raw reader, any other namespace reads and compiles through
+    // core like the listener does.
+    pub fn reader(ns: &str) -> &'static str {
+        match ns {
+            "mu" => "mu",
+            _ => "core",
         }
     }
 
-    pub fn eval_string(&self, form: String) -> std::result::Result<Tag, String> {
-        self.eval(self.compile(self.read(form)?)?)
+    // read form with the core reader from a string stream, and compile it
+    // if asked. reader conditions are reported as such either way.
+    fn core_read(&self, form: String, compile: bool) -> std::result::Result<Tag, Exception> {
+        let stream = format!("(mu:open :string :input \"{}\" :t)", Self::escape(&form));
+        let read = format!("(core:read {stream} () '%eof%)");
+
+        self.eval_string(if compile {
+            format!("(core:compile {read})")
+        } else {
+            read
+        })
+        .map_err(|ex| Exception {
+            phase: match ex.condition {
+                Condition::Eof | Condition::Read | Condition::Syntax => Phase::Read,
+                _ => Phase::Compile,
+            },
+            ..ex
+        })
     }
 
-    pub fn read(&self, form: String) -> std::result::Result<Tag, String> {
+    // the symbol holding the string stream text readers read from
+    const TEXT_READER: &str = "%rebel-reader%";
+
+    // a form that reads the next form of text each time it's evaluated,
+    // with the namespace's reader. the string stream is bound to the one
+    // text reader symbol, a new text reader rebinds it and the last
+    // text's stream goes with it. reading it is mu's job and not ours.
+    fn text_reader(&self, ns: &str, text: &str) -> std::result::Result<String, Exception> {
+        let name = Self::TEXT_READER;
+
+        self.eval_string(format!(
+            "(mu:intern (mu:find-namespace \"mu\") \"{name}\" (mu:open :string :input \"{}\" :t))",
+            Self::escape(text)
+        ))?;
+
+        Ok(match Self::reader(ns) {
+            "mu" => format!("(mu:read mu:{name} () '%eof%)"),
+            _ => format!("(core:read mu:{name} () '%eof%)"),
+        })
+    }
+
+    // the next form from a text reader, None at the end of the text
+    fn read_next(&self, reader: &str, eof: Tag) -> std::result::Result<Option<Tag>, Exception> {
+        let form = self.eval_string(reader.into()).map_err(|ex| Exception {
+            phase: Phase::Read,
+            ..ex
+        })?;
+
+        Ok((!mu::Mu::eq(form, eof)).then_some(form))
+    }
+
+    // compile a form read from a text reader. core compiles what its
+    // reader wrote back, mu compiles the form itself.
+    fn compile_in(&self, ns: &str, form: Tag) -> std::result::Result<Tag, Exception> {
+        match Self::reader(ns) {
+            "mu" => self.compile(form),
+            _ => self.core_read(self.write(form, true), true),
+        }
+    }
+
+    // evaluate each top-level form of text in order, as the reader reads
+    // them. a read error ends the text, the reader's place in it is lost.
+    pub fn eval_forms(&self, ns: &str, text: &str) -> Vec<FormResult> {
+        let mut results = Vec::new();
+
+        let reader = self
+            .eval_string("'%eof%".into())
+            .and_then(|eof| Ok((self.text_reader(ns, text)?, eof)));
+
+        match reader {
+            Err(ex) => results.push(FormResult {
+                form: text.trim().to_string(),
+                value: Err(ex),
+            }),
+            Ok((reader, eof)) => loop {
+                match self.read_next(&reader, eof) {
+                    Ok(None) => break,
+                    Ok(Some(form)) => results.push(FormResult {
+                        form: self.write(form, true),
+                        value: self
+                            .compile_in(ns, form)
+                            .and_then(|form| self.eval(form))
+                            .map(|tag| self.write(tag, true)),
+                    }),
+                    Err(ex) => {
+                        results.push(FormResult {
+                            form: String::new(),
+                            value: Err(ex),
+                        });
+                        break;
+                    }
+                }
+            },
+        }
+
+        self.flush();
+        results
+    }
+
+    // every form the reader reads from text, written back
+    fn read_forms(&self, ns: &str, text: &str) -> std::result::Result<Vec<String>, Exception> {
+        let eof = self.eval_string("'%eof%".into())?;
+        let reader = self.text_reader(ns, text)?;
+        let mut forms = Vec::new();
+
+        while let Some(form) = self.read_next(&reader, eof)? {
+            forms.push(self.write(form, true))
+        }
+
+        Ok(forms)
+    }
+
+    // text laid out from the forms the reader reads from it, each starting
+    // at column. None if writing them back spells them differently than
+    // text does, with comments or the reader's own quote forms, as then
+    // more than the layout would change.
+    pub fn pretty(
+        &self,
+        ns: &str,
+        text: &str,
+        column: usize,
+        width: usize,
+    ) -> std::result::Result<Option<String>, Exception> {
+        let forms = self.read_forms(ns, text)?;
+        let spelling = |text: &str| {
+            Syntax::tokens(text)
+                .into_iter()
+                .map(|token| text[token.start..token.end].to_string())
+                .collect::<Vec<String>>()
+        };
+
+        if spelling(&forms.join(" ")) != spelling(text) {
+            return Ok(None);
+        }
+
+        let separator = format!("\n\n{}", " ".repeat(column));
+
+        Ok(Some(
+            forms
+                .iter()
+                .map(|form| Syntax::pretty(form, column, width))
+                .collect::<Vec<String>>()
+                .join(&separator),
+        ))
+    }
+
+    // do both texts read as the same forms
+    pub fn round_trips(
+        &self,
+        ns: &str,
+        before: &str,
+        after: &str,
+    ) -> std::result::Result<bool, Exception> {
+        Ok(self.read_forms(ns, before)? == self.read_forms(ns, after)?)
+    }
+
+    // text as the body of a lisp string
+    fn escape(text: &str) -> String {
+        text.replace('\\', "\\\\").replace('"', "\\\"")
+    }
+
+    pub fn flush(&self) {
+        let _ = self.eval_string("(mu:flush mu:*standard-output*)".into());
+        let _ = self.eval_string("(mu:flush mu:*error-output*)".into());
+    }
+
+    pub fn read(&self, form: String) -> std::result::Result<Tag, Exception> {
         match mu::Mu::read_str(self.env.env, &form) {
             Ok(tag) => Ok(tag),
-            Err(ex) => Err(mu::Mu::exception_string(self.env.env, ex)),
+            Err(ex) => Err(Exception::new(self.env.env, Phase::Read, ex)),
         }
     }
 
-    pub fn compile(&self, form: Tag) -> std::result::Result<Tag, String> {
+    pub fn compile(&self, form: Tag) -> std::result::Result<Tag, Exception> {
         match mu::Mu::compile(self.env.env, form) {
             Ok(tag) => Ok(tag),
-            Err(ex) => Err(mu::Mu::exception_string(self.env.env, ex)),
+            Err(ex) => Err(Exception::new(self.env.env, Phase::Compile, ex)),
         }
     }
 
-    pub fn eval(&self, form: Tag) -> std::result::Result<Tag, String> {
+    pub fn eval(&self, form: Tag) -> std::result::Result<Tag, Exception> {
         match mu::Mu::eval(self.env.env, form) {
             Ok(tag) => Ok(tag),
-            Err(ex) => Err(mu::Mu::exception_string(self.env.env, ex)),
+            Err(ex) => Err(Exception::new(self.env.env, Phase::Eval, ex)),
         }
     }
 
@@ -76,6 +263,23 @@ impl Mu {
         mu::Mu::write_to_string(self.env.env, form, escapep)
     }
 
+    pub fn is_nil(&self, tag: Tag) -> bool {
+        self.write(tag, false) == ":nil"
+    }
+
+    pub fn eval_bool(&self, form: String) -> bool {
+        match self.eval_string(form) {
+            Ok(tag) => !self.is_nil(tag),
+            Err(_) => false,
+        }
+    }
+
+    pub fn eval_write(&self, form: String, escapep: bool) -> Option<String> {
+        self.eval_string(form)
+            .ok()
+            .map(|tag| self.write(tag, escapep))
+    }
+
     pub fn listener(&self) {
         Repl::listener(self).expect("listener: listener error");
     }
@@ -1,73 +1,405 @@
 //  SPDX-FileCopyrightText: Copyright 2024 James M. Putnam (putnamjm.design@gmail.com)
 //  SPDX-License-Identifier: MIT
 use {
-    crate::image::mu::Mu,
-    mu::{Condition, Mu as Mu_, Result},
+    crate::{
+        image::{
+            browser::Browser,
+            meta::{self, Meta},
+            mu::Mu,
+        },
+        syntax::{Context, Syntax},
+    },
+    mu::{Mu as Mu_, Result},
+    rustyline::{
+        Editor, Helper,
+        completion::{Completer, Pair},
+        error::ReadlineError,
+        highlight::Highlighter,
+        hint::Hinter,
+        history::DefaultHistory,
+        validate::{ValidationContext, ValidationResult, Validator},
+    },
+    std::{
+        cell::RefCell,
+        io::{BufRead, IsTerminal, Write},
+        ops::ControlFlow,
+        time::Instant,
+    },
 };
 
+const HISTORY_FILE: &str = ".rebel_history";
+const HISTORY_SIZE: usize = 1024;
+
+// each namespace and its symbol names
+type Names = Vec<(String, Vec<String>)>;
+
 pub trait Repl {
     fn listener(&self) -> Result;
+    fn line_listener(&self) -> Result;
+    fn stream_listener(&self) -> Result;
 }
 
 impl Repl for Mu {
+    // line editing needs a terminal, piped input is read as a stream
     fn listener(&self) -> Result {
+        if std::io::stdin().is_terminal() {
+            self.line_listener()
+        } else {
+            self.stream_listener()
+        }
+    }
+
+    fn line_listener(&self) -> Result {
         let env = self.env.env;
-        let ns = self.env.ns.clone();
 
         let eof_value = Mu_::eval_str(env, "'%eof%")?;
-        let flush_form = Mu_::compile(env, Mu_::read_str(env, "(mu:flush mu:*standard-output*)")?)?;
-
-        let read_form = match ns.as_str() {
-            "mu" => Mu_::read_str(env, "(mu:compile (mu:read mu:*standard-input* () '%eof%))")?,
-            _ => Mu_::read_str(
-                env,
-                "(core:compile (core:read mu:*standard-input* () '%eof%))",
-            )?,
+        let history = std::env::home_dir().map(|home| home.join(HISTORY_FILE));
+
+        let mut editor = match Listener::editor(self) {
+            Ok(editor) => editor,
+            Err(err) => {
+                eprintln!("rebel: no line editing, {err}");
+                return self.stream_listener();
+            }
         };
 
-        let prompt = format!("{ns}> ");
+        if let Some(path) = &history {
+            let _ = editor.load_history(path);
+        }
 
         loop {
-            Mu_::write_str(env, prompt.as_str(), Mu_::std_out())?;
-            Mu_::eval(env, flush_form)?;
+            let ns = Listener::ns(&editor);
 
-            match Mu_::eval(env, read_form) {
-                Ok(expr) => {
-                    if Mu_::eq(expr, eof_value) {
-                        break Ok(eof_value);
+            match editor.readline(&format!("{ns}> ")) {
+                Ok(line) => {
+                    if line.trim().is_empty() {
+                        continue;
                     }
 
-                    #[allow(clippy::single_match)]
-                    match Mu_::eval(env, expr) {
-                        Ok(form) => {
-                            Mu_::write(env, form, true, Mu_::std_out())?;
-                            println!()
+                    let _ = editor.add_history_entry(line.as_str());
+
+                    match Meta::parse(&line) {
+                        Some(Ok(Meta::History)) => Listener::history(editor.history().iter()),
+                        Some(Ok(meta)) => {
+                            if let Some(listener) = editor.helper_mut()
+                                && listener.meta(meta).is_break()
+                            {
+                                break;
+                            }
                         }
-                        Err(e) => {
-                            eprint!(
-                                "exception raised by {}, {:?} condition on ",
-                                Mu_::write_to_string(env, e.source, true),
-                                e.condition,
-                            );
-                            Mu_::write(env, e.object, true, Mu_::err_out())?;
-                            eprintln!()
+                        Some(Err(err)) => eprintln!("{err}"),
+                        None => Listener::eval(self, &ns, &line),
+                    }
+
+                    // anything may have been defined since the last completion
+                    if let Some(listener) = editor.helper() {
+                        listener.names.take();
+                    }
+                }
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(err) => {
+                    eprintln!("rebel: listener: {err}");
+                    break;
+                }
+            }
+        }
+
+        if let Some(path) = &history
+            && let Err(err) = editor.save_history(path)
+        {
+            eprintln!("rebel: can't save history to {}: {err}", path.display())
+        }
+
+        Ok(eof_value)
+    }
+
+    // read lines until they make complete forms, so meta commands work
+    // here too
+    fn stream_listener(&self) -> Result {
+        let eof_value = Mu_::eval_str(self.env.env, "'%eof%")?;
+
+        let mut listener = Listener::new(self);
+        let mut history = Vec::<String>::new();
+        let mut pending = String::new();
+        let mut lines = std::io::stdin().lock().lines();
+
+        loop {
+            if pending.is_empty() {
+                print!("{}> ", listener.ns);
+                let _ = std::io::stdout().flush();
+            }
+
+            let line = match lines.next() {
+                Some(Ok(line)) => line,
+                Some(Err(err)) => {
+                    eprintln!("rebel: listener: {err}");
+                    break;
+                }
+                None => break,
+            };
+
+            if pending.is_empty() {
+                match Meta::parse(&line) {
+                    Some(Ok(Meta::History)) => {
+                        Listener::history(history.iter());
+                        continue;
+                    }
+                    Some(Ok(meta)) => {
+                        history.push(line);
+                        if listener.meta(meta).is_break() {
+                            break;
                         }
+                        listener.names.take();
+                        continue;
                     }
+                    Some(Err(err)) => {
+                        eprintln!("{err}");
+                        continue;
+                    }
+                    None if line.trim().is_empty() => continue,
+                    None => (),
                 }
-                Err(e) => {
-                    if let Condition::Eof = e.condition {
-                        std::process::exit(0);
-                    } else {
-                        eprint!(
-                            "reader exception raised by {}, {:?} condition on ",
-                            Mu_::write_to_string(env, e.source, true),
-                            e.condition
-                        );
-                        Mu_::write(env, e.object, true, Mu_::err_out())?;
-                        eprintln!()
+            }
+
+            pending.push_str(&line);
+            pending.push('\n');
+
+            if Listener::is_complete(&pending) {
+                Listener::eval(self, &listener.ns, &pending);
+                history.push(std::mem::take(&mut pending).trim_end().to_string());
+                listener.names.take();
+            }
+        }
+
+        // what's left is incomplete, the reader says how
+        if !pending.trim().is_empty() {
+            Listener::eval(self, &listener.ns, &pending)
+        }
+
+        Ok(eof_value)
+    }
+}
+
+// the line editor's view of the environment, for completion and for
+// deciding when a form is complete. the symbol names are cached for
+// completion until the next command.
+struct Listener<'a> {
+    mu: &'a Mu,
+    ns: String,
+    names: RefCell<Option<Names>>,
+}
+
+impl<'a> Listener<'a> {
+    fn new(mu: &'a Mu) -> Self {
+        Self {
+            mu,
+            ns: mu.env.ns.clone(),
+            names: RefCell::new(None),
+        }
+    }
+
+    fn editor(mu: &'a Mu) -> rustyline::Result<Editor<Self, DefaultHistory>> {
+        let config = rustyline::Config::builder()
+            .max_history_size(HISTORY_SIZE)?
+            .history_ignore_dups(true)?
+            .auto_add_history(false)
+            .build();
+
+        let mut editor = Editor::with_config(config)?;
+        editor.set_helper(Some(Self::new(mu)));
+
+        Ok(editor)
+    }
+
+    fn ns(editor: &Editor<Self, DefaultHistory>) -> String {
+        editor
+            .helper()
+            .map_or_else(String::new, |listener| listener.ns.clone())
+    }
+
+    fn eval(mu: &Mu, ns: &str, text: &str) {
+        for result in mu.eval_forms(ns, text) {
+            match result.value {
+                Ok(value) => println!("{value}"),
+                Err(ex) => eprintln!("{ex}"),
+            }
+        }
+    }
+
+    fn history<'h>(history: impl Iterator<Item = &'h String>) {
+        history
+            .enumerate()
+            .for_each(|(nth, line)| println!("{nth:4}  {line}"))
+    }
+
+    // keep reading until the delimiters balance and any string or block
+    // comment is closed
+    fn is_complete(input: &str) -> bool {
+        let (_, context) = Syntax::scan(input, Context::Code);
+        let unclosed = Syntax::delimiters(input)
+            .unmatched
+            .iter()
+            .any(|token| input[token.start..].starts_with(['(', '#']));
+
+        !unclosed && context == Context::Code
+    }
+
+    // :history is the caller's, it knows where the history is kept
+    fn meta(&mut self, meta: Meta) -> ControlFlow<()> {
+        let (mu, ns) = (self.mu, self.ns.clone());
+
+        match meta {
+            Meta::Apropos(text) => self.with_names(|names| {
+                names
+                    .iter()
+                    .flat_map(|(ns, names)| {
+                        names
+                            .iter()
+                            .filter(|name| name.contains(text.as_str()))
+                            .map(move |name| format!("{ns}:{name}"))
+                    })
+                    .for_each(|name| println!("{name}"))
+            }),
+            Meta::Describe(name) => {
+                let (ns, name) = match name.split_once(':') {
+                    Some((ns, name)) if !ns.is_empty() => (ns.to_string(), name.to_string()),
+                    _ => (ns, name),
+                };
+
+                match mu
+                    .symbols(&ns)
+                    .into_iter()
+                    .find(|symbol| symbol.name == name)
+                {
+                    Some(symbol) => {
+                        println!("{ns}:{}", symbol.name);
+                        match (symbol.bound, symbol.function) {
+                            (true, true) => println!("  function {}", symbol.value),
+                            (true, false) => println!("  bound to {}", symbol.value),
+                            (false, _) => println!("  unbound"),
+                        }
                     }
+                    None => eprintln!("no symbol {name} in {ns}"),
                 }
             }
+            Meta::Help => println!("{}", meta::HELP),
+            Meta::History => (),
+            Meta::Load(path) => match mu.load(&path) {
+                Ok(true) => println!("loaded {path}"),
+                Ok(false) => eprintln!("{path} didn't load"),
+                Err(ex) => eprintln!("{ex}"),
+            },
+            Meta::Ns(None) => println!("{ns}"),
+            // only the listener's own namespace, for the reader and for
+            // bare names. mu's isn't switched.
+            Meta::Ns(Some(name)) => {
+                if mu.env.namespaces.contains(&name) {
+                    self.ns = name
+                } else {
+                    eprintln!(
+                        "no namespace {name}, known: {}",
+                        mu.env.namespaces.join(" ")
+                    )
+                }
+            }
+            Meta::Quit => return ControlFlow::Break(()),
+            Meta::Time(form) => {
+                let start = Instant::now();
+
+                Self::eval(mu, &ns, &form);
+                println!(";; {:?}", start.elapsed())
+            }
         }
+
+        ControlFlow::Continue(())
+    }
+
+    // every namespace's symbol names, asked for once per command
+    fn with_names<T>(&self, f: impl FnOnce(&[(String, Vec<String>)]) -> T) -> T {
+        let mut names = self.names.borrow_mut();
+        let names = names.get_or_insert_with(|| {
+            self.mu
+                .env
+                .namespaces
+                .iter()
+                .map(|ns| (ns.clone(), self.mu.symbol_names(ns)))
+                .collect()
+        });
+
+        f(names)
+    }
+
+    // symbols in the listener's namespace complete bare, everything the
+    // environment knows completes qualified
+    fn candidates(&self, prefix: &str) -> Vec<String> {
+        let mut candidates = self.with_names(|names| {
+            names
+                .iter()
+                .flat_map(|(ns, names)| {
+                    names.iter().flat_map(move |name| {
+                        let qualified = format!("{ns}:{name}");
+
+                        if *ns == self.ns {
+                            vec![name.clone(), qualified]
+                        } else {
+                            vec![qualified]
+                        }
+                    })
+                })
+                .filter(|candidate| candidate.starts_with(prefix))
+                .collect::<Vec<String>>()
+        });
+
+        candidates.sort();
+        candidates.dedup();
+        candidates
+    }
+}
+
+impl Completer for Listener<'_> {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let start = line[..pos]
+            .rfind(|ch: char| ch.is_whitespace() || "()'`,\"".contains(ch))
+            .map_or(0, |at| at + 1);
+
+        if start == pos {
+            return Ok((pos, Vec::new()));
+        }
+
+        Ok((
+            start,
+            self.candidates(&line[start..pos])
+                .into_iter()
+                .map(|candidate| Pair {
+                    display: candidate.clone(),
+                    replacement: candidate,
+                })
+                .collect(),
+        ))
     }
 }
+
+impl Validator for Listener<'_> {
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        Ok(match Self::is_complete(ctx.input()) {
+            true => ValidationResult::Valid(None),
+            false => ValidationResult::Incomplete,
+        })
+    }
+}
+
+impl Hinter for Listener<'_> {
+    type Hint = String;
+}
+
+impl Highlighter for Listener<'_> {}
+
+impl Helper for Listener<'_> {}
@@ -0,0 +1,119 @@
+//  SPDX-FileCopyrightText: Copyright 2025 James M. Putnam (putnamjm.design@gmail.com)
+//  SPDX-License-Identifier: MIT
+#![allow(dead_code)]
+use {
+    crate::{
+        config::Config,
+        image::{console::Console, mu::Mu},
+    },
+    std::{sync::mpsc, thread, time::Duration},
+    tokio::sync::oneshot,
+};
+
+type Job = Box<dyn FnOnce(&Mu) + Send>;
+
+// the mu environment lives on its own thread, the UI sends it jobs. a
+// stopped worker has no thread.
+pub struct Worker {
+    jobs: mpsc::Sender<Job>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Interrupt {
+    Terminated,
+    Timeout(Duration),
+}
+
+impl Worker {
+    // abandoned workers whose jobs are still running, past this many a
+    // stuck environment is kept rather than replaced
+    pub const ABANDONED_LIMIT: usize = 4;
+
+    // what the environment writes goes to console after startup and
+    // after each job
+    pub fn new(config: &Config, console: &Console) -> Self {
+        let (jobs, queue) = mpsc::channel::<Job>();
+        let config = config.clone();
+        let console = console.clone();
+
+        let thread = thread::Builder::new()
+            .name("rebel-mu".into())
+            .spawn(move || {
+                let mu = Mu::with_console(&config);
+
+                console.push(mu.env.drain());
+                for job in queue {
+                    job(&mu);
+                    console.push(mu.env.drain())
+                }
+            })
+            .expect("worker: can't spawn environment thread");
+
+        Self {
+            jobs,
+            thread: Some(thread),
+        }
+    }
+
+    // stands in for an environment whose startup was abandoned, every
+    // job is rejected as terminated
+    pub fn stopped() -> Self {
+        let (jobs, _) = mpsc::channel::<Job>();
+
+        Self { jobs, thread: None }
+    }
+
+    // mu can't interrupt a running job. an abandoned worker's thread
+    // exits once its job finishes, as nothing can send it another, and is
+    // joined here.
+    pub fn reap(abandoned: &mut Vec<Self>) {
+        let (finished, running) = std::mem::take(abandoned)
+            .into_iter()
+            .partition::<Vec<Self>, _>(|worker| {
+                worker
+                    .thread
+                    .as_ref()
+                    .is_none_or(|thread| thread.is_finished())
+            });
+
+        for thread in finished.into_iter().filter_map(|worker| worker.thread) {
+            let _ = thread.join();
+        }
+
+        *abandoned = running;
+    }
+
+    // queue a job, the future resolves with its value. a job that outlives
+    // its timeout can't be stopped, the caller is expected to abandon this
+    // worker for a new one and reap it later.
+    pub fn run<T, F>(
+        &self,
+        job: F,
+        timeout: Option<Duration>,
+    ) -> impl Future<Output = Result<T, Interrupt>> + use<T, F>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Mu) -> T + Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+
+        let sent = self.jobs.send(Box::new(move |mu| {
+            let _ = tx.send(job(mu));
+        }));
+
+        async move {
+            if sent.is_err() {
+                return Err(Interrupt::Terminated);
+            }
+
+            match timeout {
+                Some(duration) => match tokio::time::timeout(duration, rx).await {
+                    Ok(value) => value.map_err(|_| Interrupt::Terminated),
+                    Err(_) => Err(Interrupt::Timeout(duration)),
+                },
+                None => rx.await.map_err(|_| Interrupt::Terminated),
+            }
+        }
+    }
+}
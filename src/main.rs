@@ -2,22 +2,76 @@
 //  SPDX-License-Identifier: MIT
 
 //! main
+mod buffer;
+mod cli;
 mod config;
+mod environment;
+mod highlighter;
 mod image;
+mod notifications;
+mod paredit;
 mod rebel;
 mod status_line;
+mod syntax;
+#[cfg(test)]
+mod temp_dir;
 
 #[rustfmt::skip]
 use {
+    cli::{Cli, Options, USAGE},
+    config::Config,
     iced::{
         Font,
         Settings,
     },
     rebel::Rebel,
+    std::process::ExitCode,
 };
 
-fn main() -> iced::Result {
-    iced::application(Rebel::title, Rebel::update, Rebel::view)
+fn main() -> ExitCode {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("rebel: {err}\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    if options.help {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+
+    if options.version {
+        println!("rebel {}", env!("CARGO_PKG_VERSION"));
+        return ExitCode::SUCCESS;
+    }
+
+    let config = match Config::new(options.config.as_deref(), &options.overrides) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("rebel: {err}");
+            return ExitCode::from(2);
+        }
+    };
+
+    if !options.is_gui() {
+        for diagnostic in &config.diagnostics {
+            eprintln!("rebel: {diagnostic}")
+        }
+
+        if options.show_config {
+            print!("{}", config.report());
+            return ExitCode::SUCCESS;
+        }
+
+        return Cli::run(&options, &config);
+    }
+
+    let files = options.files;
+
+    let gui = iced::application(Rebel::title, Rebel::update, Rebel::view)
+        .subscription(Rebel::subscription)
         .settings(Settings {
             default_font: Font::MONOSPACE,
             fonts: vec![
@@ -27,5 +81,13 @@ fn main() -> iced::Result {
             ],
             ..Settings::default()
         })
-        .run_with(Rebel::new)
+        .run_with(move || Rebel::new(config, files));
+
+    match gui {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("rebel: {err}");
+            ExitCode::FAILURE
+        }
+    }
 }
@@ -0,0 +1,69 @@
+//  SPDX-FileCopyrightText: Copyright 2025 James M. Putnam (putnamjm.design@gmail.com)
+//  SPDX-License-Identifier: MIT
+
+//! notifications
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub level: Level,
+    pub text: String,
+}
+
+pub struct Notifications {
+    active: Vec<Notification>,
+    history: VecDeque<Notification>,
+    pub show_history: bool,
+}
+
+impl Notifications {
+    const HISTORY_LIMIT: usize = 64;
+
+    pub fn new() -> Self {
+        Self {
+            active: Vec::new(),
+            history: VecDeque::new(),
+            show_history: false,
+        }
+    }
+
+    pub fn push(&mut self, level: Level, text: impl Into<String>) {
+        let notification = Notification {
+            level,
+            text: text.into(),
+        };
+
+        if self.history.len() == Self::HISTORY_LIMIT {
+            self.history.pop_front();
+        }
+
+        self.history.push_back(notification.clone());
+        self.active.push(notification);
+    }
+
+    pub fn dismiss(&mut self, index: usize) {
+        if index < self.active.len() {
+            self.active.remove(index);
+        }
+    }
+
+    pub fn dismiss_all(&mut self) {
+        self.active.clear()
+    }
+
+    pub fn active(&self) -> &[Notification] {
+        &self.active
+    }
+
+    // most recent first
+    pub fn history(&self) -> impl Iterator<Item = &Notification> {
+        self.history.iter().rev()
+    }
+}
@@ -0,0 +1,242 @@
+//  SPDX-FileCopyrightText: Copyright 2025 James M. Putnam (putnamjm.design@gmail.com)
+//  SPDX-License-Identifier: MIT
+
+//! structural editing
+use crate::syntax::{Sexp, Syntax};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Backward,
+    Barf,
+    Forward,
+    Kill,
+    Raise,
+    Select,
+    Slurp,
+    Splice,
+    Wrap,
+}
+
+// the outcome of a command, offsets are into the new text. a motion
+// leaves the text alone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    pub text: Option<String>,
+    pub cursor: usize,
+    pub select: Option<usize>,
+}
+
+pub struct Paredit;
+
+impl Paredit {
+    // structural commands refuse to work on unbalanced text, they'd
+    // only make it worse
+    pub fn perform(command: Command, text: &str, offset: usize) -> Option<Edit> {
+        if !Syntax::delimiters(text).unmatched.is_empty() {
+            return None;
+        }
+
+        let sexps = Syntax::sexps(text);
+        let list = Self::enclosing(&sexps, offset);
+        let children = Self::children(&sexps, list);
+
+        let motion = |cursor| Edit {
+            text: None,
+            cursor,
+            select: None,
+        };
+
+        let edit = |parts: &[&str], cursor| Edit {
+            text: Some(parts.concat()),
+            cursor,
+            select: None,
+        };
+
+        match command {
+            Command::Forward => match children.iter().find(|sexp| sexp.end > offset) {
+                Some(sexp) => Some(motion(sexp.end)),
+                None => list.map(|list| motion(sexps[list].end)),
+            },
+            Command::Backward => match children.iter().rev().find(|sexp| sexp.start < offset) {
+                Some(sexp) => Some(motion(sexp.start)),
+                None => list.map(|list| motion(sexps[list].start)),
+            },
+            Command::Select => list.map(|list| Edit {
+                text: None,
+                cursor: sexps[list].start,
+                select: Some(sexps[list].end),
+            }),
+            Command::Kill => children
+                .iter()
+                .find(|sexp| sexp.end > offset)
+                .map(|sexp| edit(&[&text[..offset], &text[sexp.end..]], offset)),
+            Command::Wrap => Self::at(&children, offset).map(|sexp| {
+                edit(
+                    &[
+                        &text[..sexp.start],
+                        "(",
+                        &text[sexp.start..sexp.end],
+                        ")",
+                        &text[sexp.end..],
+                    ],
+                    sexp.start + 1,
+                )
+            }),
+            Command::Slurp => {
+                let list = &sexps[list?];
+                let close = list.close.clone()?;
+                let next = Self::children(&sexps, list.parent)
+                    .into_iter()
+                    .find(|sexp| sexp.start >= list.end)?;
+
+                Some(edit(
+                    &[
+                        &text[..close.start],
+                        &text[close.end..next.end],
+                        &text[close.clone()],
+                        &text[next.end..],
+                    ],
+                    offset,
+                ))
+            }
+            Command::Barf => {
+                let close = sexps[list?].close.clone()?;
+                let open = sexps[list?].open.clone()?;
+                let at = match children.len() {
+                    0 => return None,
+                    1 => open.end,
+                    n => children[n - 2].end,
+                };
+
+                Some(edit(
+                    &[
+                        &text[..at],
+                        &text[close.clone()],
+                        &text[at..close.start],
+                        &text[close.end..],
+                    ],
+                    if offset > at {
+                        offset + close.len()
+                    } else {
+                        offset
+                    },
+                ))
+            }
+            Command::Splice => {
+                let open = sexps[list?].open.clone()?;
+                let close = sexps[list?].close.clone()?;
+
+                Some(edit(
+                    &[
+                        &text[..open.start],
+                        &text[open.end..close.start],
+                        &text[close.end..],
+                    ],
+                    offset - open.len(),
+                ))
+            }
+            Command::Raise => {
+                let list = &sexps[list?];
+                let sexp = Self::at(&children, offset)?;
+
+                Some(edit(
+                    &[
+                        &text[..list.start],
+                        &text[sexp.start..sexp.end],
+                        &text[list.end..],
+                    ],
+                    list.start + offset.max(sexp.start) - sexp.start,
+                ))
+            }
+        }
+    }
+
+    // the innermost list whose contents hold offset
+    fn enclosing(sexps: &[Sexp], offset: usize) -> Option<usize> {
+        sexps
+            .iter()
+            .rposition(|sexp| match (&sexp.open, &sexp.close) {
+                (Some(open), Some(close)) => open.end <= offset && offset <= close.start,
+                _ => false,
+            })
+    }
+
+    fn children(sexps: &[Sexp], list: Option<usize>) -> Vec<Sexp> {
+        sexps
+            .iter()
+            .filter(|sexp| sexp.parent == list)
+            .cloned()
+            .collect()
+    }
+
+    // the element under the cursor, or the next one along
+    fn at(children: &[Sexp], offset: usize) -> Option<Sexp> {
+        children.iter().find(|sexp| sexp.end > offset).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // text with the cursor marked by a bar
+    fn perform(command: Command, marked: &str) -> Option<String> {
+        let offset = marked.find('|').unwrap();
+        let text = marked.replacen('|', "", 1);
+
+        Paredit::perform(command, &text, offset).map(|edit| {
+            let mut text = edit.text.unwrap_or(text);
+
+            text.insert(edit.cursor, '|');
+            text
+        })
+    }
+
+    #[test]
+    fn commands() {
+        let cases = [
+            (Command::Slurp, "(a |b) c", Some("(a |b c)")),
+            (Command::Slurp, "((|a) b) c", Some("((|a b)) c")),
+            (Command::Slurp, "(a |b)", None),
+            (Command::Barf, "(a b |c)", Some("(a b) |c")),
+            (Command::Barf, "(a |b c)", Some("(a |b) c")),
+            (Command::Barf, "(|a)", Some("(|)a")),
+            (Command::Barf, "(|)", None),
+            (Command::Splice, "(a (b |c) d)", Some("(a b |c d)")),
+            (Command::Splice, "a |b", None),
+            (Command::Kill, "(a |b c)", Some("(a | c)")),
+            (Command::Kill, "(a |(b c) d)", Some("(a | d)")),
+            (Command::Kill, "(a b|)", None),
+            (Command::Raise, "(a (b |c))", Some("(a |c)")),
+            (Command::Wrap, "(a |b)", Some("(a (|b))")),
+            (Command::Forward, "(|a b)", Some("(a| b)")),
+            (Command::Forward, "(a b|)", Some("(a b)|")),
+            (Command::Backward, "(a b|)", Some("(a |b)")),
+            (Command::Backward, "(|a b)", Some("|(a b)")),
+            // unbalanced text is left alone
+            (Command::Slurp, "(a |b c", None),
+            (Command::Kill, "a |b)", None),
+        ];
+
+        for (command, text, expected) in cases {
+            assert_eq!(
+                perform(command, text).as_deref(),
+                expected,
+                "{command:?} on {text:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn select() {
+        assert_eq!(
+            Paredit::perform(Command::Select, "(a (b c))", 6),
+            Some(Edit {
+                text: None,
+                cursor: 3,
+                select: Some(8),
+            })
+        );
+        assert_eq!(Paredit::perform(Command::Select, "a b", 1), None);
+    }
+}
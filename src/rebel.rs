@@ -5,41 +5,139 @@
 #[allow(unused_imports)]
 use {
     crate::{
-        config::Config,
-        image::{browser::Browser, mu::Mu},
-        status_line::StatusLine,
+        buffer::{Buffer, BufferId},
+        config::{Config, DEFAULT_ENVIRONMENT, Severity},
+        environment::{Environment, Started},
+        highlighter::{self, Highlighter},
+        image::{
+            browser::Browser,
+            console::{Console, Output, Stream},
+            env_::Startup,
+            exception::Exception,
+            mu::{FormResult, Mu},
+            worker::{Interrupt, Worker},
+        },
+        notifications::{Level, Notifications},
+        paredit::{self, Paredit},
+        syntax::{Form, Syntax},
     },
     iced::{
-        Center, Element, Font, Length, Task, Theme, keyboard,
-        widget::{button, column, container, horizontal_space, row, text, text_editor, tooltip},
+        Center, Element, Font, Length, Subscription, Task, Theme,
+        futures::future::join_all,
+        keyboard, task, time,
+        widget::{
+            button, column, container, horizontal_space, pick_list, row, scrollable, text,
+            text_editor, tooltip,
+        },
     },
     std::{
-        io,
+        fmt, io,
         path::{Path, PathBuf},
         sync::Arc,
+        time::Duration,
     },
 };
 
+// a job for one environment's worker, when several run together
+type Job<T> = Box<dyn FnOnce(&Mu) -> T + Send>;
+
 pub struct Rebel {
-    mu: Mu,
+    config: Config,
+    // the default environment first
+    environments: Vec<Environment>,
+    // the job and the environments it runs in
+    running: Option<(task::Handle, Vec<usize>)>,
+    // workers left running a cancelled or timed out job
+    abandoned: Vec<Worker>,
+    timeout: Option<Duration>,
+    console: Console,
+    output: Vec<Output>,
     browser: text_editor::Content,
-    source: text_editor::Content,
-    error: Option<Error>,
-    is_dirty: bool,
-    path: Option<PathBuf>,
-    status_line: StatusLine,
+    // environment name and results, shown side by side in place of the
+    // browser
+    comparison: Vec<(String, String)>,
+    buffers: Vec<Buffer>,
+    current: usize,
+    rainbow: bool,
+    notifications: Notifications,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     Edit(text_editor::Action),
+    BrowserAction(text_editor::Action),
     FileOpened(Result<(PathBuf, Arc<String>), Error>),
-    FileSaved(Result<PathBuf, Error>),
+    // the buffer, and where it was saved and the text it was saved with
+    FileSaved(BufferId, Result<(PathBuf, String), Error>),
+    Browsed(Result<String, Interrupt>),
+    Evaluated(Result<Vec<FormResult>, Interrupt>),
+    // the restart that started them, none at launch, and each
+    // environment's startup by index
+    Started(Option<Restart>, Vec<(usize, Result<Started, Interrupt>)>),
+    // by environment index
+    EvaluatedEach(Vec<(usize, Result<Vec<FormResult>, Interrupt>)>),
     Browse,
+    Cancel,
     Clear,
-    Eval,
+    CloseBuffer(BufferId),
+    Compare(Scope),
+    Dismiss(usize),
+    DismissAll,
+    Drain,
+    Eval(Scope),
     Load,
+    NewBuffer,
+    Restart(Restart),
     Save,
+    SaveAs,
+    SelectBuffer(usize),
+    SelectEnvironment(String),
+    SelectNamespace(String),
+    ToggleHistory,
+    ToggleRainbow,
+    ToggleReeval,
+    ShowConfig,
+    IndentLine,
+    Newline,
+    OpenParen,
+    CloseParen,
+    Paredit(paredit::Command),
+    Reformat(Scope),
+    // the buffer, its text before, and the reformatted text if it reads
+    // as the same forms
+    Reformatted(
+        BufferId,
+        Arc<String>,
+        Result<Result<Option<String>, Exception>, Interrupt>,
+    ),
+    Discard(Pending, bool),
+}
+
+// how much of the buffer a command works on
+#[derive(Debug, Clone, Copy)]
+pub enum Scope {
+    Buffer,
+    // the top-level form at the cursor
+    Form,
+    // the expression just before the cursor
+    Last,
+    Selection,
+}
+
+// how a fresh environment is made
+#[derive(Debug, Clone, Copy)]
+pub enum Restart {
+    // the current buffer's environment, from the same config
+    Reset,
+    // every environment, from the config read again
+    Reload,
+}
+
+// actions that throw away unsaved changes
+#[derive(Debug, Clone, Copy)]
+pub enum Pending {
+    Clear(BufferId),
+    Close(BufferId),
 }
 
 impl Rebel {
@@ -57,92 +155,1135 @@ impl Rebel {
         }
     }
 
-    pub fn new() -> (Self, Task<Message>) {
-        let config = Config::new();
-        let mu = Mu::new(&config);
-        let status_line = StatusLine::new(&mu);
+    // one entry per form, the form's first line and its value or exception
+    fn results(results: &[FormResult]) -> String {
+        results
+            .iter()
+            .map(|result| {
+                let mut lines = result.form.lines();
+                let form = match (lines.next(), lines.next()) {
+                    (Some(line), Some(_)) => format!("{line} ..."),
+                    (Some(line), None) => line.to_string(),
+                    _ => String::new(),
+                };
+
+                match &result.value {
+                    Ok(value) => format!("{form}\n  => {value}\n"),
+                    Err(ex) => format!(
+                        "{form}\n  !! {} {:?} on {}, raised by {}\n",
+                        ex.phase, ex.condition, ex.object, ex.source
+                    ),
+                }
+            })
+            .collect::<String>()
+    }
+
+    // run a job on the current buffer's environment, one job at a time
+    fn dispatch<T: Send + 'static>(
+        &mut self,
+        job: impl FnOnce(&Mu) -> T + Send + 'static,
+        done: impl Fn(Result<T, Interrupt>) -> Message + Send + 'static,
+    ) -> Task<Message> {
+        if self.running.is_some() {
+            return Task::none();
+        }
+
+        let index = self.environment();
+
+        if !self.environments[index].started {
+            self.unstarted(&[index]);
+            return Task::none();
+        }
+
+        let future = self.environments[index].worker.run(job, self.timeout);
+        let (task, handle) = Task::perform(future, done).abortable();
+
+        self.running = Some((handle, vec![index]));
+        self.environments[index]
+            .status_line
+            .set_activity(Some("running"));
+
+        task
+    }
+
+    // run jobs in several environments at once, the results come back
+    // together in the order given
+    fn dispatch_each<T: Send + 'static>(
+        &mut self,
+        jobs: Vec<(usize, Job<T>)>,
+        done: impl Fn(Vec<(usize, Result<T, Interrupt>)>) -> Message + Send + 'static,
+    ) -> Task<Message> {
+        if self.running.is_some() || jobs.is_empty() {
+            return Task::none();
+        }
+
+        let indices = jobs.iter().map(|(index, _)| *index).collect::<Vec<usize>>();
+        let futures = jobs
+            .into_iter()
+            .map(|(index, job)| {
+                (
+                    index,
+                    self.environments[index].worker.run(job, self.timeout),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // the environments run at once, each against its own timeout
+        let (task, handle) = Task::perform(
+            join_all(
+                futures
+                    .into_iter()
+                    .map(|(index, future)| async move { (index, future.await) }),
+            ),
+            done,
+        )
+        .abortable();
+
+        for index in &indices {
+            self.environments[*index]
+                .status_line
+                .set_activity(Some("running"));
+        }
+
+        self.running = Some((handle, indices));
+
+        task
+    }
+
+    // a stuck worker thread can't be stopped, abandon it for a fresh environment
+    fn interrupted(&mut self, interrupt: Interrupt) {
+        let indices = self
+            .running
+            .take()
+            .map_or_else(Vec::new, |(_, indices)| indices);
+
+        self.finished();
+
+        for index in indices {
+            self.abandon(index, interrupt.clone())
+        }
+    }
+
+    // a stopped environment rejects jobs, the user is told why instead
+    fn unstarted(&mut self, indices: &[usize]) {
+        for index in indices {
+            self.notifications.push(
+                Level::Warning,
+                format!(
+                    "{} didn't start, fix the config and retry",
+                    self.describe(*index)
+                ),
+            )
+        }
+    }
+
+    // named when there's more than one
+    fn describe(&self, index: usize) -> String {
+        match self.environments.len() {
+            1 => "environment".into(),
+            _ => format!("environment {}", self.environments[index].name),
+        }
+    }
+
+    // the environment's definitions go with its worker, the user has to
+    // know they're gone. stuck threads are capped, past the limit the
+    // environment is left waiting on its job instead.
+    fn abandon(&mut self, index: usize, interrupt: Interrupt) {
+        let started = self.environments[index].started;
+        let what = match started {
+            true => "evaluation",
+            false => "startup",
+        };
+        let why = match interrupt {
+            Interrupt::Terminated => format!("{what} cancelled"),
+            Interrupt::Timeout(duration) => {
+                format!("{what} timed out after {}s", duration.as_secs())
+            }
+        };
+
+        Worker::reap(&mut self.abandoned);
+
+        let environment = self.describe(index);
+
+        if self.abandoned.len() >= Worker::ABANDONED_LIMIT {
+            self.notifications.push(
+                Level::Error,
+                format!(
+                    "{why}, {environment} is still running it. {} abandoned evaluations \
+                     haven't finished, restart rebel to stop them",
+                    self.abandoned.len()
+                ),
+            );
+            return;
+        }
+
+        // a fresh worker's startup would hang the same way, the
+        // environment is left stopped and degraded until the user retries
+        let worker = match started {
+            true => Worker::new(&self.environments[index].config, &self.console),
+            false => Worker::stopped(),
+        };
+        let stuck = std::mem::replace(&mut self.environments[index].worker, worker);
+
+        self.abandoned.push(stuck);
+
+        if !started {
+            self.environments[index].startup = Startup::abandoned(why.clone());
+            self.notifications.push(
+                Level::Error,
+                format!("{why}, {environment} discarded, fix the config and retry"),
+            );
+            return;
+        }
+
+        self.notifications.push(
+            Level::Warning,
+            format!(
+                "{why}, {environment} discarded and restarted from its config, \
+                 everything defined in it since is gone"
+            ),
+        );
+    }
+
+    // a restart replaces workers that may be stuck, past the limit on
+    // abandoned ones nothing is restarted
+    fn can_restart(&mut self) -> bool {
+        Worker::reap(&mut self.abandoned);
+
+        if self.abandoned.len() >= Worker::ABANDONED_LIMIT {
+            self.notifications.push(
+                Level::Error,
+                format!(
+                    "can't restart, {} abandoned evaluations haven't finished, restart rebel \
+                     to stop them",
+                    self.abandoned.len()
+                ),
+            );
+            return false;
+        }
+
+        true
+    }
+
+    // workers a restart replaced are tracked with the abandoned ones, an
+    // idle one's thread exits as soon as it's dropped
+    fn retire(&mut self, workers: impl IntoIterator<Item = Worker>) {
+        self.abandoned.extend(workers)
+    }
+
+    fn notify_error(&mut self, error: Error) {
+        let level = match error {
+            Error::DialogClosed => Level::Info,
+            Error::IOFailed(_) => Level::Error,
+        };
+
+        self.notifications.push(level, error.to_string())
+    }
+
+    fn drain(&mut self) {
+        for output in self.console.drain() {
+            Console::append(&mut self.output, output, Console::LIMIT)
+        }
+    }
+
+    fn finished(&mut self) {
+        self.drain();
+        self.running = None;
+
+        for environment in &mut self.environments {
+            environment.status_line.set_activity(None)
+        }
+    }
+
+    // the current buffer's environment, the default if its own is gone
+    fn environment(&self) -> usize {
+        self.environment_named(&self.buffer().env)
+    }
+
+    fn environment_named(&self, name: &str) -> usize {
+        self.environments
+            .iter()
+            .position(|environment| environment.name == name)
+            .unwrap_or(0)
+    }
+
+    // a new buffer evaluates where the current one does
+    fn new_buffer(&self, text: &str, path: Option<PathBuf>) -> Buffer {
+        let environment = match self.buffers.is_empty() {
+            true => &self.environments[0],
+            false => &self.environments[self.environment()],
+        };
+
+        Buffer::new(text, path, &environment.name, &environment.ns)
+    }
+
+    // one notification for all the forms raising exceptions, how many and
+    // the first, named for its environment when there's more than one
+    fn notify_exceptions(&mut self, index: usize, results: &[FormResult]) {
+        let exceptions = results
+            .iter()
+            .filter(|result| result.value.is_err())
+            .count();
+
+        if let Some(Err(ex)) = results
+            .iter()
+            .map(|result| &result.value)
+            .find(|value| value.is_err())
+        {
+            let env = match self.environments.len() {
+                1 => String::new(),
+                _ => format!(" in {}", self.environments[index].name),
+            };
+
+            self.notifications.push(
+                Level::Error,
+                format!(
+                    "{exceptions} of {} forms raised exceptions{env}, first: {ex}",
+                    results.len()
+                ),
+            )
+        }
+    }
+
+    // reformatted lists that don't fit break one element to a line
+    const REFORMAT_WIDTH: usize = 80;
+
+    // the part of the current buffer a scope names, the editor doesn't say
+    // where a selection is so it has no region
+    fn region(&self, scope: Scope) -> Option<Form> {
+        let text = self.buffer().content.text();
+        let (line, column) = self.buffer().cursor();
+        let offset = Syntax::offset(&text, line, column);
+
+        match scope {
+            Scope::Buffer => Some(Form {
+                start: 0,
+                end: text.len(),
+            }),
+            Scope::Form => Syntax::form_at(&text, offset),
+            Scope::Last => Syntax::sexp_before(&text, offset),
+            Scope::Selection => None,
+        }
+    }
+
+    // the text a scope names in the current buffer, if there's any
+    fn source(&self, scope: Scope) -> Option<String> {
+        let text = self.buffer().content.text();
+
+        match scope {
+            Scope::Buffer => Some(text),
+            Scope::Selection => self.buffer().content.selection(),
+            _ => self
+                .region(scope)
+                .map(|form| text[form.start..form.end].to_string()),
+        }
+        .filter(|source| !source.trim().is_empty())
+    }
+
+    fn browse_text(&mut self, text: String) {
+        self.comparison.clear();
+        self.browser = text_editor::Content::with_text(&Self::pad_lines(text, 30));
+    }
+
+    fn show(&mut self, text: String) {
+        self.finished();
+        self.browse_text(text);
+    }
+
+    // config problems are notifications
+    fn diagnose(&mut self) {
+        let diagnostics = self
+            .environments
+            .iter()
+            .flat_map(|environment| &environment.config.diagnostics)
+            .chain(&self.config.diagnostics);
+
+        for diagnostic in diagnostics {
+            self.notifications.push(
+                match diagnostic.severity {
+                    Severity::Warning => Level::Warning,
+                    Severity::Error => Level::Error,
+                },
+                diagnostic.to_string(),
+            )
+        }
+    }
+
+    fn is_degraded(&self) -> bool {
+        self.environments
+            .iter()
+            .any(|environment| environment.startup.is_degraded())
+    }
+
+    // each environment's startup report, by name when there's more than one
+    fn startup_report(&self, degraded: bool) -> String {
+        self.environments
+            .iter()
+            .filter(|environment| !degraded || environment.startup.is_degraded())
+            .map(|environment| match self.environments.len() {
+                1 => environment.startup.to_string(),
+                _ => format!("{}: {}", environment.name, environment.startup),
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    // a degraded startup opens its report in the browser pane
+    fn report(&mut self) {
+        let degraded = self
+            .environments
+            .iter()
+            .filter(|environment| environment.startup.is_degraded())
+            .map(|environment| environment.name.clone())
+            .collect::<Vec<String>>();
+
+        if !degraded.is_empty() {
+            self.notifications.push(
+                Level::Error,
+                match self.environments.len() {
+                    1 => "environment started degraded, fix the config and retry".to_string(),
+                    _ => format!(
+                        "environments started degraded: {}, fix the config and retry",
+                        degraded.join(", ")
+                    ),
+                },
+            );
+            self.browse_text(self.startup_report(true));
+        }
+    }
+
+    // startup runs as a job like any other, so a hung rc file or module
+    // load times out instead of freezing the gui
+    fn start(&mut self, indices: Vec<usize>, restart: Option<Restart>) -> Task<Message> {
+        let jobs = indices
+            .iter()
+            .map(|index| (*index, Box::new(Started::new) as Job<Started>))
+            .collect();
+        let task = self.dispatch_each(jobs, move |results| Message::Started(restart, results));
+
+        for index in indices {
+            self.environments[index]
+                .status_line
+                .set_activity(Some("starting"))
+        }
+
+        task
+    }
+
+    // the buffers stay as they are, the ones marked for it are evaluated
+    // again in the restarted environments. a buffer whose namespace is
+    // gone from its environment takes the environment's.
+    fn restarted(&mut self, indices: Vec<usize>) -> Task<Message> {
+        self.browse_text(String::new());
+        self.report();
+
+        let mut jobs: Vec<(usize, Job<Vec<FormResult>>)> = Vec::new();
+
+        for index in indices {
+            let environment = &self.environments[index];
+            let mut moved = Vec::new();
+
+            for buffer in &mut self.buffers {
+                if buffer.env == environment.name && !environment.namespaces.contains(&buffer.ns) {
+                    moved.push(format!(
+                        "{} from {} to {}",
+                        buffer.name(),
+                        buffer.ns,
+                        environment.ns
+                    ));
+                    buffer.ns = environment.ns.clone();
+                }
+            }
+
+            if !moved.is_empty() {
+                self.notifications.push(
+                    Level::Warning,
+                    format!(
+                        "namespaces gone from {}, buffers moved: {}",
+                        self.describe(index),
+                        moved.join(", ")
+                    ),
+                )
+            }
+
+            let sources = self
+                .buffers
+                .iter()
+                .filter(|buffer| buffer.reeval && self.environment_named(&buffer.env) == index)
+                .map(|buffer| (buffer.ns.clone(), buffer.content.text()))
+                .collect::<Vec<(String, String)>>();
+
+            if self.environments[index].startup.is_degraded() {
+                if !sources.is_empty() {
+                    self.notifications.push(
+                        Level::Warning,
+                        format!(
+                            "{} started degraded, {} marked buffers not evaluated again",
+                            self.describe(index),
+                            sources.len()
+                        ),
+                    )
+                }
+                continue;
+            }
+
+            self.notifications
+                .push(Level::Info, format!("{} restarted", self.describe(index)));
+
+            if !sources.is_empty() {
+                jobs.push((
+                    index,
+                    Box::new(move |mu| {
+                        sources
+                            .iter()
+                            .flat_map(|(ns, source)| mu.eval_forms(ns, source))
+                            .collect()
+                    }),
+                ))
+            }
+        }
+
+        self.dispatch_each(jobs, Message::EvaluatedEach)
+    }
+
+    // files from the command line open as buffers once the gui is up
+    pub fn new(config: Config, files: Vec<PathBuf>) -> (Self, Task<Message>) {
+        let console = Console::new();
+        let environments = Environment::all(&config, &console);
+        let default = &environments[0];
+        let buffer = Buffer::new(
+            &Self::pad_lines(String::new(), 30),
+            None,
+            &default.name,
+            &default.ns,
+        );
+
+        let mut rebel = Self {
+            timeout: config.eval_timeout,
+            rainbow: config.rainbow,
+            config,
+            environments,
+            running: None,
+            abandoned: Vec::new(),
+            console,
+            output: Vec::new(),
+            browser: text_editor::Content::with_text(&Self::pad_lines(String::new(), 30)),
+            comparison: Vec::new(),
+            buffers: vec![buffer],
+            current: 0,
+            notifications: Notifications::new(),
+        };
+
+        rebel.diagnose();
+
+        let start = rebel.start((0..rebel.environments.len()).collect(), None);
 
         (
-            Self {
-                mu,
-                path: None,
-                source: text_editor::Content::with_text(&Self::pad_lines(String::new(), 30)),
-                browser: text_editor::Content::with_text(&Self::pad_lines(String::new(), 30)),
-                error: None,
-                is_dirty: true,
-                status_line,
-            },
-            Task::none(),
+            rebel,
+            Task::batch(
+                files
+                    .into_iter()
+                    .map(|path| Task::perform(Self::load_buffer(path), Message::FileOpened))
+                    .chain([start, Task::done(Message::Drain)]),
+            ),
         )
     }
 
+    // pick up each environment's output as its job finishes
+    pub fn subscription(&self) -> Subscription<Message> {
+        if self.running.is_some() {
+            time::every(Duration::from_millis(250)).map(|_| Message::Drain)
+        } else {
+            Subscription::none()
+        }
+    }
+
     pub fn title(&self) -> String {
-        format!("rebel: {}", env!("CARGO_PKG_VERSION"))
+        let buffer = self.buffer();
+
+        format!(
+            "rebel: {} - {}{}",
+            env!("CARGO_PKG_VERSION"),
+            buffer.name(),
+            if buffer.is_dirty { " *" } else { "" }
+        )
+    }
+
+    fn buffer(&self) -> &Buffer {
+        &self.buffers[self.current]
+    }
+
+    fn buffer_mut(&mut self) -> &mut Buffer {
+        &mut self.buffers[self.current]
+    }
+
+    fn position(&self, id: BufferId) -> Option<usize> {
+        self.buffers.iter().position(|buffer| buffer.id == id)
+    }
+
+    fn save(&self, path: Option<PathBuf>) -> Task<Message> {
+        let buffer = self.buffer();
+        let id = buffer.id;
+
+        Task::perform(
+            Self::save_buffer(path, buffer.content.text()),
+            move |result| Message::FileSaved(id, result),
+        )
+    }
+
+    // ask before discarding a dirty buffer
+    fn discard(&mut self, pending: Pending) -> Task<Message> {
+        let (Pending::Clear(id) | Pending::Close(id)) = pending;
+        let is_dirty = self
+            .position(id)
+            .is_some_and(|index| self.buffers[index].is_dirty);
+
+        if is_dirty {
+            Task::perform(Self::confirm_discard(), move |yes| {
+                Message::Discard(pending, yes)
+            })
+        } else {
+            Task::done(Message::Discard(pending, true))
+        }
     }
 
     pub fn update(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::Edit(action) => {
-                self.is_dirty = self.is_dirty || action.is_edit();
-                self.source.perform(action);
-                self.error = None;
+                let buffer = self.buffer_mut();
+
+                buffer.is_dirty = buffer.is_dirty || action.is_edit();
+                buffer.content.perform(action);
+                buffer.refresh();
+
+                Task::none()
+            }
+            // the browser pane is read-only
+            Message::BrowserAction(action) => {
+                if !action.is_edit() {
+                    self.browser.perform(action);
+                }
 
                 Task::none()
             }
             Message::Browse => {
-                self.path = None;
-                self.source = text_editor::Content::new();
-                self.is_dirty = true;
+                let selection = self.buffer().content.selection().unwrap_or("()".into());
+
+                self.dispatch(
+                    move |mu| match mu.read(selection) {
+                        Ok(tag) => mu.browse(tag),
+                        Err(ex) => ex.to_string(),
+                    },
+                    Message::Browsed,
+                )
+            }
+            Message::Browsed(Ok(listing)) => {
+                self.show(listing);
+
+                Task::none()
+            }
+            Message::Cancel => {
+                if let Some((handle, _)) = &self.running {
+                    handle.abort();
+                    self.interrupted(Interrupt::Terminated)
+                }
+
+                Task::none()
+            }
+            Message::Clear => self.discard(Pending::Clear(self.buffer().id)),
+            Message::CloseBuffer(id) => self.discard(Pending::Close(id)),
+            Message::Discard(Pending::Clear(id), true) => {
+                if let Some(index) = self.position(id) {
+                    let buffer = &self.buffers[index];
+
+                    self.buffers[index] = Buffer::new(
+                        &Self::pad_lines(String::new(), 30),
+                        None,
+                        &buffer.env,
+                        &buffer.ns,
+                    );
+                }
+
+                Task::none()
+            }
+            Message::Discard(Pending::Close(id), true) => {
+                if let Some(index) = self.position(id) {
+                    self.buffers.remove(index);
+
+                    if self.buffers.is_empty() {
+                        self.buffers
+                            .push(self.new_buffer(&Self::pad_lines(String::new(), 30), None));
+                    }
+
+                    if self.current >= index && self.current > 0 {
+                        self.current -= 1;
+                    }
+                }
+
+                Task::none()
+            }
+            Message::Discard(_, false) => Task::none(),
+            Message::Restart(_) if self.running.is_some() => Task::none(),
+            Message::Restart(_) if !self.can_restart() => Task::none(),
+            Message::Restart(Restart::Reset) => {
+                let index = self.environment();
+                let worker = self.environments[index].reset();
+
+                self.retire([worker]);
+                self.start(vec![index], Some(Restart::Reset))
+            }
+            // the config is read again, so fixes to the files take effect.
+            // buffers in an environment it no longer names move to the
+            // default.
+            Message::Restart(Restart::Reload) => match self.config.reload() {
+                Ok(config) => {
+                    self.timeout = config.eval_timeout;
+                    self.rainbow = config.rainbow;
+
+                    let environments = std::mem::replace(
+                        &mut self.environments,
+                        Environment::all(&config, &self.console),
+                    );
+
+                    self.retire(
+                        environments
+                            .into_iter()
+                            .map(|environment| environment.worker),
+                    );
+                    self.config = config;
+
+                    for buffer in &mut self.buffers {
+                        if !self.environments.iter().any(|env| env.name == buffer.env) {
+                            buffer.env = DEFAULT_ENVIRONMENT.into()
+                        }
+                    }
+
+                    self.diagnose();
+                    self.start(
+                        (0..self.environments.len()).collect(),
+                        Some(Restart::Reload),
+                    )
+                }
+                Err(err) => {
+                    self.notifications.push(Level::Error, err);
+
+                    Task::none()
+                }
+            },
+            // buffers made at launch have the placeholder namespace, they
+            // take their environment's. a restart keeps what they chose.
+            Message::Started(restart, results) => {
+                self.finished();
+
+                let mut indices = Vec::new();
+
+                for (index, result) in results {
+                    match result {
+                        Ok(started) => {
+                            self.environments[index].started(started);
+                            indices.push(index)
+                        }
+                        Err(interrupt) => self.abandon(index, interrupt),
+                    }
+                }
+
+                match restart {
+                    Some(_) => self.restarted(indices),
+                    None => {
+                        for index in indices {
+                            let environment = &self.environments[index];
+
+                            for buffer in &mut self.buffers {
+                                if buffer.env == environment.name {
+                                    buffer.ns = environment.ns.clone()
+                                }
+                            }
+                        }
+
+                        self.report();
+
+                        Task::none()
+                    }
+                }
+            }
+            Message::NewBuffer => {
+                self.buffers
+                    .push(self.new_buffer(&Self::pad_lines(String::new(), 30), None));
+                self.current = self.buffers.len() - 1;
 
                 Task::none()
             }
-            Message::Clear => {
-                self.path = None;
-                self.source = text_editor::Content::with_text(&Self::pad_lines(String::new(), 30));
-                self.browser = text_editor::Content::with_text(&Self::pad_lines(String::new(), 30));
-                self.is_dirty = true;
+            Message::SelectBuffer(index) => {
+                if index < self.buffers.len() {
+                    self.current = index;
+                }
 
                 Task::none()
             }
-            Message::Eval => {
-                let text = self.source.text();
+            // the buffer keeps its namespace if the environment has it
+            Message::SelectEnvironment(name) => {
+                let index = self.environment_named(&name);
+                let ns = self.environments[index]
+                    .ns_for(&self.buffer().ns)
+                    .to_string();
+                let buffer = self.buffer_mut();
+
+                buffer.env = name;
+                buffer.ns = ns;
+
+                Task::none()
+            }
+            Message::SelectNamespace(ns) => {
+                self.buffer_mut().ns = ns;
+
+                Task::none()
+            }
+            Message::Dismiss(index) => {
+                self.notifications.dismiss(index);
+
+                Task::none()
+            }
+            Message::DismissAll => {
+                self.notifications.dismiss_all();
+
+                Task::none()
+            }
+            Message::Newline => {
+                let buffer = self.buffer_mut();
+                let text = buffer.content.text();
+                let (line, column) = buffer.cursor();
+                let indent = Syntax::indent(&text, Syntax::offset(&text, line, column));
+
+                buffer.is_dirty = true;
+                buffer
+                    .content
+                    .perform(text_editor::Action::Edit(text_editor::Edit::Enter));
+                if indent > 0 {
+                    buffer
+                        .content
+                        .perform(text_editor::Action::Edit(text_editor::Edit::Paste(
+                            Arc::new(" ".repeat(indent)),
+                        )));
+                }
+                buffer.refresh();
+
+                Task::none()
+            }
+            // typing an open paren brings its close along, typing the close
+            // steps over one that's already there
+            // delimiters pair up in code, in a string, comment or after #\
+            // they're just characters. a selection is wrapped.
+            Message::OpenParen => {
+                let buffer = self.buffer_mut();
+                let in_literal = buffer.in_literal();
+
+                buffer.is_dirty = true;
+                match buffer.content.selection() {
+                    Some(selection) if !in_literal => {
+                        buffer
+                            .content
+                            .perform(text_editor::Action::Edit(text_editor::Edit::Paste(
+                                Arc::new(format!("({selection})")),
+                            )))
+                    }
+                    _ if in_literal => buffer
+                        .content
+                        .perform(text_editor::Action::Edit(text_editor::Edit::Insert('('))),
+                    _ => {
+                        buffer.content.perform(text_editor::Action::Edit(
+                            text_editor::Edit::Paste(Arc::new("()".into())),
+                        ));
+                        buffer
+                            .content
+                            .perform(text_editor::Action::Move(text_editor::Motion::Left));
+                    }
+                }
+                buffer.refresh();
+
+                Task::none()
+            }
+            Message::CloseParen => {
+                let buffer = self.buffer_mut();
+                let (line, column) = buffer.cursor();
+                let at_close = buffer
+                    .content
+                    .line(line)
+                    .is_some_and(|text| text[column..].starts_with(')'));
+
+                if at_close && buffer.content.selection().is_none() && !buffer.in_literal() {
+                    buffer
+                        .content
+                        .perform(text_editor::Action::Move(text_editor::Motion::Right));
+                } else {
+                    buffer.is_dirty = true;
+                    buffer
+                        .content
+                        .perform(text_editor::Action::Edit(text_editor::Edit::Insert(')')));
+                }
+                buffer.refresh();
+
+                Task::none()
+            }
+            Message::Paredit(command) => {
+                let buffer = self.buffer_mut();
+                let text = buffer.content.text();
+                let (line, column) = buffer.cursor();
+
+                match Paredit::perform(command, &text, Syntax::offset(&text, line, column)) {
+                    Some(edit) => self.buffer_mut().apply(edit),
+                    None if self.buffer().unclosed + self.buffer().unopened > 0 => {
+                        self.notifications.push(
+                            Level::Warning,
+                            "unbalanced delimiters, structural edit refused",
+                        )
+                    }
+                    None => (),
+                }
+
+                Task::none()
+            }
+            Message::IndentLine => {
+                let buffer = self.buffer_mut();
+                let (line, _) = buffer.cursor();
+                let text = buffer.reindented(line..line + 1);
+
+                buffer.apply_indent(&text);
 
-                self.browser = text_editor::Content::with_text(&Self::pad_lines(
-                    match self.mu.eval_string(text) {
-                        Ok(tag) => self.mu.write(tag, false),
-                        Err(err) => err,
+                Task::none()
+            }
+            // pretty-printed from the reader's forms where that only changes
+            // the layout, re-indented otherwise, and kept only if the
+            // result reads the same
+            Message::Reformat(scope) => {
+                let buffer = self.buffer();
+                let text = buffer.content.text();
+
+                let Some(region) = self.region(scope) else {
+                    return Task::none();
+                };
+
+                let source = &text[region.start..region.end];
+                let start = region.start + (source.len() - source.trim_start().len());
+                let end = region.start + source.trim_end().len();
+
+                let (line, column) = Syntax::position(&text, start);
+                let lines = line..Syntax::position(&text, end).0 + 1;
+                let column = text[start - column..start].chars().count();
+
+                let id = buffer.id;
+                let ns = buffer.ns.clone();
+                let reindented = buffer.reindented(lines);
+                let before = Arc::new(text);
+                let before_ = Arc::clone(&before);
+
+                self.dispatch(
+                    move |mu| {
+                        let after = match mu.pretty(
+                            &ns,
+                            &before_[start..end],
+                            column,
+                            Self::REFORMAT_WIDTH,
+                        )? {
+                            Some(pretty) => {
+                                format!("{}{pretty}{}", &before_[..start], &before_[end..])
+                            }
+                            None => reindented,
+                        };
+
+                        Ok(mu.round_trips(&ns, &before_, &after)?.then_some(after))
                     },
-                    30,
+                    move |result| Message::Reformatted(id, before.clone(), result),
+                )
+            }
+            Message::Reformatted(id, before, Ok(reformatted)) => {
+                self.finished();
+
+                match reformatted {
+                    Ok(Some(after)) => {
+                        if let Some(index) = self.position(id) {
+                            let buffer = &mut self.buffers[index];
+
+                            // the buffer changed underneath us, let it be
+                            if buffer.content.text() == *before && *before != after {
+                                buffer.apply_indent(&after)
+                            }
+                        }
+                    }
+                    Ok(None) => self.notifications.push(
+                        Level::Warning,
+                        "reformatting would change what the reader sees, buffer left as is",
+                    ),
+                    Err(ex) => self
+                        .notifications
+                        .push(Level::Error, format!("can't reformat: {ex}")),
+                }
+
+                Task::none()
+            }
+            Message::Reformatted(_, _, Err(interrupt)) => {
+                self.interrupted(interrupt);
+
+                Task::none()
+            }
+            Message::ShowConfig => {
+                self.browse_text(format!(
+                    "effective config\n\n{}\n{}",
+                    self.config.report(),
+                    self.startup_report(false)
                 ));
 
                 Task::none()
             }
+            Message::ToggleReeval => {
+                let buffer = self.buffer_mut();
+
+                buffer.reeval = !buffer.reeval;
+
+                Task::none()
+            }
+            Message::ToggleRainbow => {
+                self.rainbow = !self.rainbow;
+
+                Task::none()
+            }
+            Message::ToggleHistory => {
+                self.notifications.show_history = !self.notifications.show_history;
+
+                Task::none()
+            }
+            Message::Drain => {
+                self.drain();
+
+                Task::none()
+            }
+            Message::Eval(scope) => {
+                let ns = self.buffer().ns.clone();
+
+                match self.source(scope) {
+                    Some(source) => {
+                        self.dispatch(move |mu| mu.eval_forms(&ns, &source), Message::Evaluated)
+                    }
+                    None => {
+                        self.notifications.push(Level::Info, "nothing to evaluate");
+
+                        Task::none()
+                    }
+                }
+            }
+            Message::Evaluated(Ok(results)) => {
+                let index = self.running.as_ref().map_or(0, |(_, indices)| indices[0]);
+
+                self.notify_exceptions(index, &results);
+                self.show(Self::results(&results));
+
+                Task::none()
+            }
+            // the same source in every environment, each in the buffer's
+            // namespace if it has it
+            Message::Compare(scope) => {
+                let Some(source) = self.source(scope) else {
+                    self.notifications.push(Level::Info, "nothing to compare");
+
+                    return Task::none();
+                };
+
+                let ns = self.buffer().ns.clone();
+                let unstarted = (0..self.environments.len())
+                    .filter(|index| !self.environments[*index].started)
+                    .collect::<Vec<usize>>();
+
+                self.unstarted(&unstarted);
+
+                let jobs = self
+                    .environments
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, environment)| environment.started)
+                    .map(|(index, environment)| {
+                        let ns = environment.ns_for(&ns).to_string();
+                        let source = source.clone();
+                        let job: Job<Vec<FormResult>> =
+                            Box::new(move |mu| mu.eval_forms(&ns, &source));
+
+                        (index, job)
+                    })
+                    .collect();
+
+                self.dispatch_each(jobs, Message::EvaluatedEach)
+            }
+            // one environment's results go to the browser, several side by side
+            Message::EvaluatedEach(results) => {
+                self.finished();
+
+                let mut columns = Vec::new();
+
+                for (index, result) in results {
+                    let text = match result {
+                        Ok(results) => {
+                            self.notify_exceptions(index, &results);
+                            Self::results(&results)
+                        }
+                        Err(interrupt) => {
+                            let text = match &interrupt {
+                                Interrupt::Terminated => "cancelled".to_string(),
+                                Interrupt::Timeout(duration) => {
+                                    format!("timed out after {}s", duration.as_secs())
+                                }
+                            };
+
+                            self.abandon(index, interrupt);
+                            text
+                        }
+                    };
+
+                    columns.push((self.environments[index].name.clone(), text))
+                }
+
+                match columns.len() {
+                    1 => self.browse_text(columns.remove(0).1),
+                    _ => self.comparison = columns,
+                }
+
+                Task::none()
+            }
+            Message::Browsed(Err(interrupt)) | Message::Evaluated(Err(interrupt)) => {
+                self.interrupted(interrupt);
+
+                Task::none()
+            }
             Message::Load => Task::perform(Self::pick_file(), Message::FileOpened),
             Message::FileOpened(Ok((path, content))) => {
-                self.path = Some(path);
-                self.source = text_editor::Content::with_text(&content);
-                self.is_dirty = false;
+                let buffer = self.new_buffer(&content, Some(path));
+
+                if self.buffer().is_scratch() {
+                    self.buffers[self.current] = buffer;
+                } else {
+                    self.buffers.push(buffer);
+                    self.current = self.buffers.len() - 1;
+                }
 
                 Task::none()
             }
             Message::FileOpened(Err(error)) => {
-                self.error = Some(error);
+                self.notify_error(error);
 
                 Task::none()
             }
-            Message::Save => {
-                let text = self.source.text();
+            Message::Save => self.save(self.buffer().path.clone()),
+            Message::SaveAs => self.save(None),
+            // edits made while the save was running are still unsaved
+            Message::FileSaved(id, Ok((path, text))) => {
+                if let Some(index) = self.position(id) {
+                    let buffer = &mut self.buffers[index];
 
-                Task::perform(Self::save_buffer(None, text), Message::FileSaved)
-            }
-            Message::FileSaved(Ok(path)) => {
-                self.path = Some(path);
-                self.is_dirty = false;
+                    buffer.path = Some(path);
+                    buffer.is_dirty = buffer.content.text() != text;
+                }
 
                 Task::none()
             }
-            Message::FileSaved(Err(error)) => {
-                self.error = Some(error);
+            Message::FileSaved(_, Err(error)) => {
+                self.notify_error(error);
 
                 Task::none()
             }
@@ -150,56 +1291,400 @@ impl Rebel {
     }
 
     pub fn view(&self) -> Element<'_, Message> {
+        let environment = &self.environments[self.environment()];
         let controls = row![
+            Self::action(
+                Self::icon('\u{002B}'),
+                "new buffer",
+                Some(Message::NewBuffer)
+            ),
             Self::action(Self::icon('\u{E01E}'), "clear buffer", Some(Message::Clear)),
             Self::action(Self::icon('\u{003A}'), "load buffer", Some(Message::Load)),
             Self::action(
                 Self::icon('\u{e055}'),
                 "save buffer",
-                self.is_dirty.then_some(Message::Save)
+                self.buffer().is_dirty.then_some(Message::Save)
+            ),
+            Self::action(
+                Self::icon('\u{003B}'),
+                "save buffer as",
+                Some(Message::SaveAs)
+            ),
+            Self::action(
+                Self::icon('\u{0034}'),
+                "eval buffer",
+                self.running
+                    .is_none()
+                    .then_some(Message::Eval(Scope::Buffer))
+            ),
+            Self::action(
+                Self::icon('\u{0064}'),
+                "reformat buffer",
+                self.running
+                    .is_none()
+                    .then_some(Message::Reformat(Scope::Buffer))
+            ),
+            Self::action(
+                Self::icon('\u{E017}'),
+                if self.buffer().reeval {
+                    "don't re-evaluate buffer on restart"
+                } else {
+                    "re-evaluate buffer on restart"
+                },
+                Some(Message::ToggleReeval)
+            ),
+            Self::action(
+                Self::icon('\u{E049}'),
+                "compare form across environments",
+                (self.running.is_none() && self.environments.len() > 1)
+                    .then_some(Message::Compare(Scope::Form))
             ),
-            Self::action(Self::icon('\u{0034}'), "eval buffer", Some(Message::Eval)),
             Self::action(
                 Self::icon('\u{E010}'),
                 "browse selection",
-                Some(Message::Browse)
+                self.running.is_none().then_some(Message::Browse)
+            ),
+            Self::action(
+                Self::icon('\u{0039}'),
+                "cancel evaluation",
+                self.running.is_some().then_some(Message::Cancel)
+            ),
+            horizontal_space(),
+            self.environment_list(),
+            tooltip(
+                pick_list(
+                    environment.namespaces.as_slice(),
+                    Some(&self.buffer().ns),
+                    Message::SelectNamespace
+                )
+                .text_size(14),
+                "buffer namespace",
+                tooltip::Position::FollowCursor,
+            ),
+            Self::action(
+                Self::icon('\u{004C}'),
+                "rainbow delimiters",
+                Some(Message::ToggleRainbow)
+            ),
+            Self::action(
+                Self::icon('\u{007E}'),
+                "effective config",
+                Some(Message::ShowConfig)
+            ),
+            Self::action(
+                Self::icon('\u{E036}'),
+                "reset environment",
+                self.running
+                    .is_none()
+                    .then_some(Message::Restart(Restart::Reset))
+            ),
+            Self::action(
+                Self::icon('\u{005A}'),
+                if self.is_degraded() {
+                    "retry environment startup"
+                } else {
+                    "restart with reloaded config"
+                },
+                self.running
+                    .is_none()
+                    .then_some(Message::Restart(Restart::Reload))
+            ),
+            Self::action(
+                Self::icon('\u{0078}'),
+                "notification history",
+                Some(Message::ToggleHistory)
             ),
         ]
         .spacing(10)
         .align_y(Center);
 
-        let input = text_editor(&self.source)
+        let input = text_editor(&self.buffer().content)
             .placeholder("")
             .on_action(Message::Edit)
             .key_binding(|key_press| match key_press.key.as_ref() {
+                keyboard::Key::Character("s" | "S")
+                    if key_press.modifiers.command() && key_press.modifiers.shift() =>
+                {
+                    Some(text_editor::Binding::Custom(Message::SaveAs))
+                }
                 keyboard::Key::Character("s") if key_press.modifiers.command() => {
                     Some(text_editor::Binding::Custom(Message::Save))
                 }
+                keyboard::Key::Character("f" | "F")
+                    if key_press.modifiers.command() && key_press.modifiers.shift() =>
+                {
+                    Some(text_editor::Binding::Custom(Message::Reformat(Scope::Form)))
+                }
+                keyboard::Key::Named(keyboard::key::Named::Enter)
+                    if key_press.modifiers.command() && key_press.modifiers.shift() =>
+                {
+                    Some(text_editor::Binding::Custom(Message::Eval(
+                        Scope::Selection,
+                    )))
+                }
+                keyboard::Key::Named(keyboard::key::Named::Enter)
+                    if key_press.modifiers.command() && key_press.modifiers.alt() =>
+                {
+                    Some(text_editor::Binding::Custom(Message::Compare(Scope::Form)))
+                }
+                keyboard::Key::Named(keyboard::key::Named::Enter)
+                    if key_press.modifiers.command() =>
+                {
+                    Some(text_editor::Binding::Custom(Message::Eval(Scope::Form)))
+                }
+                keyboard::Key::Character("e") if key_press.modifiers.command() => {
+                    Some(text_editor::Binding::Custom(Message::Eval(Scope::Last)))
+                }
+                keyboard::Key::Named(keyboard::key::Named::Enter)
+                    if key_press.modifiers.is_empty() =>
+                {
+                    Some(text_editor::Binding::Custom(Message::Newline))
+                }
+                keyboard::Key::Named(keyboard::key::Named::Tab)
+                    if key_press.modifiers.is_empty() =>
+                {
+                    Some(text_editor::Binding::Custom(Message::IndentLine))
+                }
+                keyboard::Key::Character(key)
+                    if key_press.modifiers.command() || key_press.modifiers.alt() =>
+                {
+                    let modifiers = key_press.modifiers;
+                    let command = match key {
+                        ")" if modifiers.command() => paredit::Command::Slurp,
+                        "}" if modifiers.command() => paredit::Command::Barf,
+                        "f" if modifiers.command() && modifiers.alt() => paredit::Command::Forward,
+                        "b" if modifiers.command() && modifiers.alt() => paredit::Command::Backward,
+                        "k" if modifiers.command() => paredit::Command::Kill,
+                        "(" if modifiers.alt() => paredit::Command::Wrap,
+                        "s" if modifiers.alt() => paredit::Command::Splice,
+                        "r" if modifiers.alt() => paredit::Command::Raise,
+                        _ => return text_editor::Binding::from_key_press(key_press),
+                    };
+
+                    Some(text_editor::Binding::Custom(Message::Paredit(command)))
+                }
+                keyboard::Key::Named(keyboard::key::Named::ArrowUp)
+                    if key_press.modifiers.alt() =>
+                {
+                    Some(text_editor::Binding::Custom(Message::Paredit(
+                        paredit::Command::Select,
+                    )))
+                }
+                keyboard::Key::Character("(") => {
+                    Some(text_editor::Binding::Custom(Message::OpenParen))
+                }
+                keyboard::Key::Character(")") => {
+                    Some(text_editor::Binding::Custom(Message::CloseParen))
+                }
                 _ => text_editor::Binding::from_key_press(key_press),
-            });
+            })
+            .highlight_with::<Highlighter>(
+                highlighter::Settings {
+                    buffer: Some(self.buffer().id),
+                    rainbow: self.rainbow,
+                    marks: self.buffer().marks.clone(),
+                },
+                Highlighter::to_format,
+            );
 
         let browser = text_editor(&self.browser)
             .placeholder("")
-            .on_action(Message::Edit)
+            .on_action(Message::BrowserAction)
             .key_binding(|key_press| match key_press.key.as_ref() {
+                keyboard::Key::Character("s" | "S")
+                    if key_press.modifiers.command() && key_press.modifiers.shift() =>
+                {
+                    Some(text_editor::Binding::Custom(Message::SaveAs))
+                }
                 keyboard::Key::Character("s") if key_press.modifiers.command() => {
                     Some(text_editor::Binding::Custom(Message::Save))
                 }
                 _ => text_editor::Binding::from_key_press(key_press),
-            });
+            })
+            .highlight_with::<Highlighter>(
+                highlighter::Settings {
+                    rainbow: self.rainbow,
+                    ..highlighter::Settings::default()
+                },
+                Highlighter::to_format,
+            );
+
+        let console = container(
+            scrollable(
+                column(self.output.iter().map(|output| {
+                    let text = text(output.text.trim_end_matches('\n'));
+
+                    match output.stream {
+                        Stream::Out => text.into(),
+                        Stream::Err => text.style(text::danger).into(),
+                    }
+                }))
+                .width(Length::Fill),
+            )
+            .anchor_bottom()
+            .height(Length::Fixed(120.0)),
+        )
+        .padding(5)
+        .style(container::bordered_box);
 
-        let status = self.status_line.content();
+        let status = row![
+            text(match self.environments.len() {
+                1 => environment.status_line.content(),
+                _ => format!(
+                    "{}: {}",
+                    environment.name,
+                    environment.status_line.content()
+                ),
+            }),
+            horizontal_space(),
+            match self.buffer().balance() {
+                Some(balance) => text(format!("parens: {balance}")).style(text::danger),
+                None => text("parens: balanced").style(text::secondary),
+            }
+        ];
 
         self::column![
             controls,
-            self::row![input, browser].spacing(10),
-            text(status)
+            self.notifications(),
+            self.tabs(),
+            self::row![input, self.comparison(browser)].spacing(10),
+            console,
+            status
         ]
         .spacing(10)
         .padding(10)
         .into()
     }
 
+    fn tabs(&self) -> Element<'_, Message> {
+        row(self.buffers.iter().enumerate().map(|(index, buffer)| {
+            let is_current = index == self.current;
+            let label = format!(
+                "{}{} [{}{}]",
+                buffer.name(),
+                if buffer.is_dirty { " *" } else { "" },
+                buffer.ns,
+                if buffer.reeval { ", re-eval" } else { "" }
+            );
+
+            row![
+                button(text(label))
+                    .padding([2, 10])
+                    .style(if is_current {
+                        button::primary
+                    } else {
+                        button::secondary
+                    })
+                    .on_press(Message::SelectBuffer(index)),
+                button(text("x"))
+                    .padding([2, 6])
+                    .style(button::text)
+                    .on_press(Message::CloseBuffer(buffer.id)),
+            ]
+            .align_y(Center)
+            .into()
+        }))
+        .spacing(5)
+        .into()
+    }
+
+    fn notifications(&self) -> Element<'_, Message> {
+        let style = |level: Level| match level {
+            Level::Info => text::secondary,
+            Level::Warning => text::primary,
+            Level::Error => text::danger,
+        };
+
+        let active = self
+            .notifications
+            .active()
+            .iter()
+            .enumerate()
+            .map(|(index, notification)| {
+                row![
+                    text(&notification.text)
+                        .style(style(notification.level))
+                        .width(Length::Fill),
+                    button(text("dismiss")).on_press(Message::Dismiss(index)),
+                ]
+                .spacing(10)
+                .align_y(Center)
+                .into()
+            });
+
+        let mut notifications = column(active).spacing(5);
+
+        if self.notifications.active().len() > 1 {
+            notifications = notifications.push(row![
+                horizontal_space(),
+                button(text("dismiss all")).on_press(Message::DismissAll)
+            ]);
+        }
+
+        if self.notifications.show_history {
+            notifications = notifications.push(
+                container(
+                    scrollable(
+                        column(self.notifications.history().map(|notification| {
+                            text(&notification.text)
+                                .style(style(notification.level))
+                                .into()
+                        }))
+                        .width(Length::Fill),
+                    )
+                    .height(Length::Fixed(100.0)),
+                )
+                .padding(5)
+                .style(container::bordered_box),
+            );
+        }
+
+        notifications.into()
+    }
+
+    // only worth showing when the config names more than one
+    fn environment_list(&self) -> Element<'_, Message> {
+        if self.environments.len() < 2 {
+            return horizontal_space().width(0).into();
+        }
+
+        tooltip(
+            pick_list(
+                self.environments
+                    .iter()
+                    .map(|environment| environment.name.clone())
+                    .collect::<Vec<String>>(),
+                Some(self.environments[self.environment()].name.clone()),
+                Message::SelectEnvironment,
+            )
+            .text_size(14),
+            "buffer environment",
+            tooltip::Position::FollowCursor,
+        )
+        .into()
+    }
+
+    // a comparison replaces the browser until something else is shown
+    fn comparison<'a>(&'a self, browser: impl Into<Element<'a, Message>>) -> Element<'a, Message> {
+        if self.comparison.is_empty() {
+            return browser.into();
+        }
+
+        row(self.comparison.iter().map(|(name, results)| {
+            container(column![
+                text(name).style(text::secondary),
+                scrollable(text(results).font(Font::MONOSPACE)).height(Length::Fill),
+            ])
+            .padding(5)
+            .width(Length::Fill)
+            .style(container::bordered_box)
+            .into()
+        }))
+        .spacing(10)
+        .width(Length::Fill)
+        .into()
+    }
+
     fn action<'a>(
         content: Element<'a, Message>,
         label: &'a str,
@@ -246,6 +1731,17 @@ impl Rebel {
         Self::load_buffer(handle.path().to_owned()).await
     }
 
+    async fn confirm_discard() -> bool {
+        rfd::AsyncMessageDialog::new()
+            .set_title("Unsaved changes")
+            .set_description("The buffer has unsaved changes. Discard them?")
+            .set_level(rfd::MessageLevel::Warning)
+            .set_buttons(rfd::MessageButtons::YesNo)
+            .show()
+            .await
+            == rfd::MessageDialogResult::Yes
+    }
+
     async fn load_buffer(path: PathBuf) -> Result<(PathBuf, Arc<String>), Error> {
         let contents = tokio::fs::read_to_string(&path)
             .await
@@ -256,7 +1752,7 @@ impl Rebel {
         Ok((path, contents))
     }
 
-    async fn save_buffer(path: Option<PathBuf>, text: String) -> Result<PathBuf, Error> {
+    async fn save_buffer(path: Option<PathBuf>, text: String) -> Result<(PathBuf, String), Error> {
         let path = if let Some(path) = path {
             path
         } else {
@@ -268,11 +1764,11 @@ impl Rebel {
                 .map(|handle| handle.path().to_owned())?
         };
 
-        tokio::fs::write(&path, text)
+        tokio::fs::write(&path, &text)
             .await
             .map_err(|error| Error::IOFailed(error.kind()))?;
 
-        Ok(path)
+        Ok((path, text))
     }
 }
 
@@ -282,3 +1778,12 @@ pub enum Error {
     DialogClosed,
     IOFailed(io::ErrorKind),
 }
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::DialogClosed => f.write_str("file dialog closed, nothing was done"),
+            Error::IOFailed(kind) => write!(f, "file operation failed: {kind}"),
+        }
+    }
+}
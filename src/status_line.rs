@@ -2,64 +2,81 @@
 //  SPDX-License-Identifier: MIT
 
 //! status line
-use crate::{
-    config::ConfigOpt,
-    image::mu::Mu,
-};
+use crate::image::{env_::Env_, mu::Mu};
 
+#[derive(Debug, Clone)]
 pub struct StatusLine {
     mu_version: String,
     core_version: String,
     module_versions: String,
+    activity: Option<String>,
 }
 
 impl StatusLine {
     pub fn content(&self) -> String {
-        format!("mu {}, {}{}", self.mu_version, self.core_version, self.module_versions)
+        let versions = match self.mu_version.is_empty() {
+            true => "mu".to_string(),
+            false => format!(
+                "mu {}, {}{}",
+                self.mu_version, self.core_version, self.module_versions
+            ),
+        };
+
+        match &self.activity {
+            Some(activity) => format!("{versions} | {activity}"),
+            None => versions,
+        }
+    }
+
+    pub fn set_activity(&mut self, activity: Option<&str>) {
+        self.activity = activity.map(String::from)
+    }
+
+    // nothing to show but the activity until the environment has started
+    pub fn starting() -> Self {
+        Self {
+            mu_version: String::new(),
+            core_version: String::new(),
+            module_versions: String::new(),
+            activity: Some("starting".into()),
+        }
     }
 
     pub fn new(mu: &Mu) -> Self {
         let mu_version: String = mu.version();
 
-        let core_version: String = match mu.map_config("namespace") {
-            Some(ns) => {                
-                let version = mu.write(
-                    mu.eval_string("(mu:cdr (core:assq 'version core:%sys-def))".into())
-                        .unwrap(),
-                    false,
-                );
-                format!("{ns} {version}")
-            }
-            None => "core.sys not loaded, browsing is disabled".into(),
+        let version = |ns: &str| {
+            mu.eval_write(
+                format!("(mu:cdr (core:assq 'version {ns}:%sys-def))"),
+                false,
+            )
+            .unwrap_or_else(|| "unknown".into())
         };
 
-        let module_versions = match mu.map_config_opt("modules") {
-            Some(opt) => match opt {
-                ConfigOpt::Array(modules) => modules
-                    .iter()
-                    .map(|module| match module {
-                        ConfigOpt::String(str) => {
-                            let mut ns = String::from(str);
-                            ns.truncate(str.len() - 4);
-                            let version = mu.write(
-                                mu.eval_string(format!("(mu:cdr (core:assq 'version {ns}:%sys-def))").into())
-                                    .unwrap(),
-                                false,
-                            );
-                            format!(", {ns} {version}")
-                        },
-                        _ => panic!(),
-                    })
-                    .collect::<String>(),
-                _ => panic!(),
-            }
-            None => String::new(),
+        // only what actually loaded, startup may have skipped some
+        let loaded = |ns: &str| mu.env.namespaces.iter().any(|loaded| loaded == ns);
+
+        let core_version: String = if loaded("core") {
+            format!("{} {}", mu.env.ns, version("core"))
+        } else {
+            "core.sys not loaded, browsing is disabled".into()
         };
-                     
+
+        let module_versions = mu
+            .env
+            .config
+            .modules
+            .iter()
+            .map(|module| Env_::module_ns(module))
+            .filter(|ns| loaded(ns))
+            .map(|ns| format!(", {ns} {}", version(&ns)))
+            .collect::<String>();
+
         Self {
             mu_version,
             core_version,
             module_versions,
+            activity: None,
         }
     }
 }
@@ -0,0 +1,853 @@
+//  SPDX-FileCopyrightText: Copyright 2025 James M. Putnam (putnamjm.design@gmail.com)
+//  SPDX-License-Identifier: MIT
+
+//! mu source syntax
+#![allow(dead_code)]
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Atom,
+    Char,
+    Close,
+    Comment,
+    Open,
+    Prefix,
+    String,
+}
+
+// byte offsets into the scanned text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Atom {
+    Keyword,
+    Number,
+    // the namespace prefix's length, including the colon
+    Qualified(usize),
+    Symbol,
+}
+
+// matched delimiter pairs and the delimiters left without a partner
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Delimiters {
+    pub pairs: Vec<(Token, Token)>,
+    pub unmatched: Vec<Token>,
+}
+
+// an s-expression, prefixes included. lists carry the byte ranges of
+// their delimiters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sexp {
+    pub start: usize,
+    pub end: usize,
+    pub open: Option<std::ops::Range<usize>>,
+    pub close: Option<std::ops::Range<usize>>,
+    pub parent: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Form {
+    pub start: usize,
+    pub end: usize,
+}
+
+// where a scan left off, strings and block comments may span lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Context {
+    #[default]
+    Code,
+    String,
+    Comment,
+}
+
+// forms that get body indentation and a heavier face
+pub const SPECIAL_FORMS: &[&str] = &[
+    "and", "cond", "defconst", "defmacro", "defun", "deftype", "flet", "if", "labels", "lambda",
+    "let", "let*", "or", "progn", "unless", "when", ":lambda", ":if",
+];
+
+// an open list while indenting, columns are in characters
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Frame {
+    column: usize,
+    head: Option<(String, usize, usize)>,
+    arg: Option<usize>,
+}
+
+pub struct Syntax;
+
+type Chars<'a> = std::iter::Peekable<std::str::CharIndices<'a>>;
+
+impl Syntax {
+    fn is_delimiter(ch: char) -> bool {
+        ch.is_whitespace() || matches!(ch, '(' | ')' | '"' | ';' | '\'' | '`' | ',')
+    }
+
+    // consume the rest of a string, true if it was terminated
+    fn scan_string(chars: &mut Chars) -> bool {
+        while let Some((_, ch)) = chars.next() {
+            match ch {
+                '\\' => {
+                    chars.next();
+                }
+                '"' => return true,
+                _ => (),
+            }
+        }
+
+        false
+    }
+
+    // consume the rest of a block comment, true if it was terminated
+    fn scan_comment(chars: &mut Chars) -> bool {
+        let mut bar = false;
+
+        for (_, ch) in chars.by_ref() {
+            if bar && ch == '#' {
+                return true;
+            }
+            bar = ch == '|';
+        }
+
+        false
+    }
+
+    fn end(chars: &mut Chars, text: &str) -> usize {
+        match chars.peek() {
+            Some((end, _)) => *end,
+            None => text.len(),
+        }
+    }
+
+    pub fn atom(atom: &str) -> Atom {
+        let digits = atom.strip_prefix(['-', '+']).unwrap_or(atom);
+
+        if atom.starts_with(':') {
+            Atom::Keyword
+        } else if digits.starts_with(|ch: char| ch.is_ascii_digit())
+            && (atom.parse::<i64>().is_ok() || atom.parse::<f64>().is_ok())
+            || atom.starts_with("#x")
+        {
+            Atom::Number
+        } else {
+            match atom.find(':') {
+                Some(colon) => Atom::Qualified(colon + 1),
+                None => Atom::Symbol,
+            }
+        }
+    }
+
+    pub fn tokens(text: &str) -> Vec<Token> {
+        Self::scan(text, Context::Code).0
+    }
+
+    // scan text starting in context, returning its tokens and the
+    // context the scan ended in
+    pub fn scan(text: &str, context: Context) -> (Vec<Token>, Context) {
+        let mut tokens = Vec::new();
+        let mut chars = text.char_indices().peekable();
+
+        let continued = match context {
+            Context::Code => None,
+            Context::String => Some((TokenKind::String, Self::scan_string(&mut chars))),
+            Context::Comment => Some((TokenKind::Comment, Self::scan_comment(&mut chars))),
+        };
+
+        if let Some((kind, terminated)) = continued {
+            tokens.push(Token {
+                kind,
+                start: 0,
+                end: Self::end(&mut chars, text),
+            });
+
+            if !terminated {
+                return (tokens, context);
+            }
+        }
+
+        let mut context = Context::Code;
+
+        while let Some((start, ch)) = chars.next() {
+            let kind = match ch {
+                _ if ch.is_whitespace() => continue,
+                '(' => TokenKind::Open,
+                ')' => TokenKind::Close,
+                '\'' | '`' => TokenKind::Prefix,
+                ',' => {
+                    chars.next_if(|(_, ch)| *ch == '@');
+                    TokenKind::Prefix
+                }
+                ';' => {
+                    while chars.next_if(|(_, ch)| *ch != '\n').is_some() {}
+                    TokenKind::Comment
+                }
+                '"' => {
+                    if !Self::scan_string(&mut chars) {
+                        context = Context::String
+                    }
+                    TokenKind::String
+                }
+                '#' => match chars.peek() {
+                    Some((_, '(')) => {
+                        chars.next();
+                        TokenKind::Open
+                    }
+                    Some((_, '\\')) => {
+                        chars.next();
+                        if let Some((_, ch)) = chars.next()
+                            && ch.is_alphanumeric()
+                        {
+                            while chars.next_if(|(_, ch)| ch.is_alphanumeric()).is_some() {}
+                        }
+                        TokenKind::Char
+                    }
+                    Some((_, '|')) => {
+                        chars.next();
+                        if !Self::scan_comment(&mut chars) {
+                            context = Context::Comment
+                        }
+                        TokenKind::Comment
+                    }
+                    _ => {
+                        while chars.next_if(|(_, ch)| !Self::is_delimiter(*ch)).is_some() {}
+                        TokenKind::Atom
+                    }
+                },
+                _ => {
+                    while chars.next_if(|(_, ch)| !Self::is_delimiter(*ch)).is_some() {}
+                    TokenKind::Atom
+                }
+            };
+
+            tokens.push(Token {
+                kind,
+                start,
+                end: Self::end(&mut chars, text),
+            });
+        }
+
+        (tokens, context)
+    }
+
+    // would a character typed at offset land in a string, a comment or a
+    // character literal, rather than in code
+    pub fn in_literal(text: &str, offset: usize) -> bool {
+        Self::tokens(text).iter().any(|token| {
+            let body = &text[token.start..token.end];
+            // at a token's end is still inside it if it isn't closed
+            let inside = |closed: bool| {
+                token.start < offset && (offset < token.end || offset == token.end && !closed)
+            };
+            let closed = || Self::scan(body, Context::Code).1 == Context::Code;
+
+            match token.kind {
+                TokenKind::String => inside(closed()),
+                TokenKind::Comment => inside(!body.starts_with(';') && closed()),
+                TokenKind::Char => offset == token.end && body == "#\\",
+                _ => false,
+            }
+        })
+    }
+
+    // split text into its top-level forms, an unbalanced trailing form
+    // extends to the end of the text and is left for the reader to reject
+    pub fn forms(text: &str) -> Vec<Form> {
+        let mut forms = Vec::new();
+        let mut depth: usize = 0;
+        let mut start: Option<usize> = None;
+
+        for token in Self::tokens(text) {
+            if token.kind == TokenKind::Comment {
+                continue;
+            }
+
+            let start_ = *start.get_or_insert(token.start);
+
+            match token.kind {
+                TokenKind::Open => depth += 1,
+                TokenKind::Close => depth = depth.saturating_sub(1),
+                TokenKind::Prefix => continue,
+                _ => (),
+            }
+
+            if depth == 0 {
+                forms.push(Form {
+                    start: start_,
+                    end: token.end,
+                });
+                start = None;
+            }
+        }
+
+        if let Some(start) = start {
+            forms.push(Form {
+                start,
+                end: text.trim_end().len(),
+            });
+        }
+
+        forms
+    }
+
+    // the top-level form containing offset, or ending just before it
+    pub fn form_at(text: &str, offset: usize) -> Option<Form> {
+        Self::forms(text)
+            .into_iter()
+            .find(|form| form.start <= offset && offset <= form.end)
+    }
+
+    // the s-expression ending nearest before offset, the outermost if
+    // several end there
+    pub fn sexp_before(text: &str, offset: usize) -> Option<Form> {
+        Self::sexps(text)
+            .into_iter()
+            .filter(|sexp| sexp.end <= offset)
+            .min_by_key(|sexp| (offset - sexp.end, sexp.start))
+            .map(|sexp| Form {
+                start: sexp.start,
+                end: sexp.end,
+            })
+    }
+
+    // the s-expressions of text in pre-order, an unclosed list runs to
+    // the end of the text
+    pub fn sexps(text: &str) -> Vec<Sexp> {
+        let mut sexps = Vec::<Sexp>::new();
+        let mut lists = Vec::<usize>::new();
+        let mut prefix: Option<usize> = None;
+
+        for token in Self::tokens(text) {
+            match token.kind {
+                TokenKind::Comment => (),
+                TokenKind::Prefix => {
+                    prefix.get_or_insert(token.start);
+                }
+                TokenKind::Close => {
+                    if let Some(list) = lists.pop() {
+                        sexps[list].end = token.end;
+                        sexps[list].close = Some(token.start..token.end);
+                    }
+                }
+                _ => {
+                    let is_list = token.kind == TokenKind::Open;
+
+                    sexps.push(Sexp {
+                        start: prefix.take().unwrap_or(token.start),
+                        end: if is_list { text.len() } else { token.end },
+                        open: is_list.then_some(token.start..token.end),
+                        close: None,
+                        parent: lists.last().copied(),
+                    });
+
+                    if is_list {
+                        lists.push(sexps.len() - 1)
+                    }
+                }
+            }
+        }
+
+        sexps
+    }
+
+    pub fn delimiters(text: &str) -> Delimiters {
+        let mut delimiters = Delimiters::default();
+        let mut opens = Vec::<Token>::new();
+
+        for token in Self::tokens(text) {
+            match token.kind {
+                TokenKind::Open => opens.push(token),
+                TokenKind::Close => match opens.pop() {
+                    Some(open) => delimiters.pairs.push((open, token)),
+                    None => delimiters.unmatched.push(token),
+                },
+                _ => (),
+            }
+        }
+
+        delimiters.unmatched.extend(opens);
+        delimiters.unmatched.sort_by_key(|token| token.start);
+        delimiters
+    }
+
+    // the delimiter under the cursor, or just behind it, and its partner
+    pub fn matching(delimiters: &Delimiters, offset: usize) -> Option<(Token, Option<Token>)> {
+        [false, true].into_iter().find_map(|behind| {
+            let test = |token: &Token| {
+                if behind {
+                    token.end == offset
+                } else {
+                    token.start == offset
+                }
+            };
+
+            delimiters
+                .pairs
+                .iter()
+                .find_map(|(open, close)| {
+                    if test(open) {
+                        Some((*open, Some(*close)))
+                    } else if test(close) {
+                        Some((*close, Some(*open)))
+                    } else {
+                        None
+                    }
+                })
+                .or_else(|| {
+                    delimiters
+                        .unmatched
+                        .iter()
+                        .find(|token| test(token))
+                        .map(|token| (*token, None))
+                })
+        })
+    }
+
+    // byte offset of a (line, byte column) position
+    pub fn offset(text: &str, line: usize, column: usize) -> usize {
+        let start = text
+            .split_inclusive('\n')
+            .take(line)
+            .map(str::len)
+            .sum::<usize>();
+
+        (start + column).min(text.len())
+    }
+
+    // (line, byte column) position of a byte offset
+    pub fn position(text: &str, offset: usize) -> (usize, usize) {
+        let before = &text[..offset.min(text.len())];
+
+        match before.rfind('\n') {
+            Some(newline) => (before.matches('\n').count(), offset - newline - 1),
+            None => (0, offset),
+        }
+    }
+
+    fn is_special(head: &str) -> bool {
+        let name = match Self::atom(head) {
+            Atom::Qualified(ns) => &head[ns..],
+            _ => head,
+        };
+
+        SPECIAL_FORMS.contains(&name)
+    }
+
+    // feed a line to the open list stack, returning the context it ends in
+    fn frames(frames: &mut Vec<Frame>, line_no: usize, line: &str, context: Context) -> Context {
+        let (tokens, context) = Self::scan(line, context);
+        let column = |offset: usize| line[..offset].chars().count();
+        let mut prefix: Option<usize> = None;
+
+        for token in tokens {
+            let element = prefix.take().unwrap_or(token.start);
+
+            match token.kind {
+                TokenKind::Comment => continue,
+                TokenKind::Prefix => {
+                    prefix.get_or_insert(token.start);
+                    continue;
+                }
+                TokenKind::Close => {
+                    frames.pop();
+                    continue;
+                }
+                _ => (),
+            }
+
+            if let Some(frame) = frames.last_mut() {
+                match &frame.head {
+                    None => {
+                        frame.head = Some((
+                            line[token.start..token.end].to_string(),
+                            column(element),
+                            line_no,
+                        ))
+                    }
+                    Some((_, _, head_line)) if frame.arg.is_none() && *head_line == line_no => {
+                        frame.arg = Some(column(element))
+                    }
+                    _ => (),
+                }
+            }
+
+            if token.kind == TokenKind::Open {
+                frames.push(Frame {
+                    column: column(token.start),
+                    head: None,
+                    arg: None,
+                })
+            }
+        }
+
+        context
+    }
+
+    fn frame_indent(frame: Option<&Frame>) -> usize {
+        match frame {
+            None => 0,
+            Some(frame) => match (&frame.head, frame.arg) {
+                (None, _) => frame.column + 1,
+                (Some((head, _, _)), _) if Self::is_special(head) => frame.column + 2,
+                (Some(_), Some(arg)) => arg,
+                (Some((_, column, _)), None) => *column,
+            },
+        }
+    }
+
+    // the indentation of a new line started at offset
+    pub fn indent(text: &str, offset: usize) -> usize {
+        let mut frames = Vec::new();
+        let mut context = Context::Code;
+
+        for (line_no, line) in text[..offset].split('\n').enumerate() {
+            context = Self::frames(&mut frames, line_no, line, context);
+        }
+
+        match context {
+            Context::Code => Self::frame_indent(frames.last()),
+            _ => 0,
+        }
+    }
+
+    // the elements of a special form kept on its first line
+    fn header(head: &str) -> usize {
+        let name = match Self::atom(head) {
+            Atom::Qualified(ns) => &head[ns..],
+            _ => head,
+        };
+
+        match name {
+            "defconst" | "defmacro" | "defun" | "deftype" => 2,
+            "and" | "cond" | "or" | "progn" => 0,
+            _ => 1,
+        }
+    }
+
+    // lay out a form that starts at column, breaking the lists that don't
+    // fit in width one element to a line and indenting them as indent
+    // would. comments aren't expected, this is for forms the reader wrote.
+    pub fn pretty(text: &str, column: usize, width: usize) -> String {
+        let sexps = Self::sexps(text);
+
+        match sexps.iter().position(|sexp| sexp.parent.is_none()) {
+            Some(root) => Self::layout(text, &sexps, root, column, width),
+            None => text.trim().to_string(),
+        }
+    }
+
+    fn layout(text: &str, sexps: &[Sexp], nth: usize, column: usize, width: usize) -> String {
+        let sexp = &sexps[nth];
+        let flat = &text[sexp.start..sexp.end];
+
+        let (Some(open), Some(close)) = (&sexp.open, &sexp.close) else {
+            return flat.to_string();
+        };
+
+        if column + flat.chars().count() <= width && !flat.contains('\n') {
+            return flat.to_string();
+        }
+
+        let elements = sexps
+            .iter()
+            .enumerate()
+            .filter(|(_, sexp)| sexp.parent == Some(nth))
+            .map(|(nth, _)| nth)
+            .collect::<Vec<usize>>();
+
+        let Some((&head, args)) = elements.split_first() else {
+            return flat.to_string();
+        };
+
+        let width_of = |text: &str| text.chars().count();
+        let open_column = column + width_of(&text[sexp.start..open.start]);
+        let first_column = column + width_of(&text[sexp.start..open.end]);
+        let head_text = &text[sexps[head].start..sexps[head].end];
+
+        // where the rest go, and how many args stay on the head's line
+        let (body, kept) = match sexps[head].open {
+            Some(_) => (first_column, 0),
+            None if Self::is_special(head_text) => {
+                (open_column + 2, Self::header(head_text).min(args.len()))
+            }
+            None if args.is_empty() => (first_column, 0),
+            None => (first_column + width_of(head_text) + 1, 1),
+        };
+
+        let mut pretty = text[sexp.start..open.end].to_string() + head_text;
+        let mut at = first_column + width_of(head_text);
+
+        for arg in &args[..kept] {
+            let arg = Self::layout(text, sexps, *arg, at + 1, width);
+
+            at = match arg.rsplit_once('\n') {
+                Some((_, last)) => width_of(last),
+                None => at + 1 + width_of(&arg),
+            };
+            pretty.push(' ');
+            pretty.push_str(&arg);
+        }
+
+        for arg in &args[kept..] {
+            pretty.push('\n');
+            pretty.push_str(&" ".repeat(body));
+            pretty.push_str(&Self::layout(text, sexps, *arg, body, width));
+        }
+
+        pretty + &text[close.clone()]
+    }
+
+    // re-indent the lines in range, lines that start inside a string or
+    // block comment are left alone
+    pub fn reindent(text: &str, lines: std::ops::Range<usize>) -> String {
+        let mut frames = Vec::new();
+        let mut context = Context::Code;
+
+        let reindented = text
+            .split('\n')
+            .enumerate()
+            .map(|(line_no, line)| {
+                let line = if lines.contains(&line_no) && context == Context::Code {
+                    let trimmed = line.trim();
+
+                    if trimmed.is_empty() {
+                        String::new()
+                    } else {
+                        let indent = Self::frame_indent(frames.last());
+
+                        " ".repeat(indent) + trimmed
+                    }
+                } else {
+                    line.to_string()
+                };
+
+                context = Self::frames(&mut frames, line_no, &line, context);
+                line
+            })
+            .collect::<Vec<String>>();
+
+        reindented.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(text: &str) -> Vec<(TokenKind, &str)> {
+        Syntax::tokens(text)
+            .into_iter()
+            .map(|token| (token.kind, &text[token.start..token.end]))
+            .collect()
+    }
+
+    fn forms(text: &str) -> Vec<&str> {
+        Syntax::forms(text)
+            .into_iter()
+            .map(|form| &text[form.start..form.end])
+            .collect()
+    }
+
+    #[test]
+    fn strings_with_escaped_quotes() {
+        assert_eq!(
+            kinds(r#""a \"b\" c" x"#),
+            [
+                (TokenKind::String, r#""a \"b\" c""#),
+                (TokenKind::Atom, "x")
+            ]
+        );
+        assert_eq!(kinds(r#""\\" y"#)[1], (TokenKind::Atom, "y"));
+    }
+
+    #[test]
+    fn characters() {
+        assert_eq!(
+            kinds(r"(#\( #\space #\))"),
+            [
+                (TokenKind::Open, "("),
+                (TokenKind::Char, r"#\("),
+                (TokenKind::Char, r"#\space"),
+                (TokenKind::Char, r"#\)"),
+                (TokenKind::Close, ")"),
+            ]
+        );
+        assert_eq!(forms(r"#\( x"), [r"#\(", "x"]);
+        assert_eq!(Syntax::delimiters(r"(#\()").unmatched, []);
+    }
+
+    #[test]
+    fn comments() {
+        assert_eq!(
+            kinds("(a ; b)\nc)"),
+            [
+                (TokenKind::Open, "("),
+                (TokenKind::Atom, "a"),
+                (TokenKind::Comment, "; b)"),
+                (TokenKind::Atom, "c"),
+                (TokenKind::Close, ")"),
+            ]
+        );
+        assert_eq!(
+            kinds("#| a ( |# b"),
+            [(TokenKind::Comment, "#| a ( |#"), (TokenKind::Atom, "b")]
+        );
+        assert_eq!(forms("; all comment\n(a) ; after\n"), ["(a)"]);
+    }
+
+    #[test]
+    fn continued_contexts() {
+        assert_eq!(Syntax::scan("a \"b", Context::Code).1, Context::String);
+        assert_eq!(Syntax::scan("#| a", Context::Code).1, Context::Comment);
+
+        let (tokens, context) = Syntax::scan("b\" c", Context::String);
+
+        assert_eq!(context, Context::Code);
+        assert_eq!(
+            tokens.iter().map(|token| token.kind).collect::<Vec<_>>(),
+            [TokenKind::String, TokenKind::Atom]
+        );
+        assert_eq!(Syntax::scan("b |#", Context::Comment).1, Context::Code);
+    }
+
+    #[test]
+    fn nested_and_unbalanced_forms() {
+        assert_eq!(forms("(a (b c)) 'd (e"), ["(a (b c))", "'d", "(e"]);
+        assert_eq!(forms("a)  b"), ["a", ")", "b"]);
+        assert_eq!(forms("(a\n  (b)\n"), ["(a\n  (b)"]);
+    }
+
+    #[test]
+    fn sexps() {
+        let sexps = Syntax::sexps("'(a (b))");
+
+        assert_eq!(sexps.len(), 4);
+        assert_eq!((sexps[0].start, sexps[0].end), (0, 8));
+        assert_eq!(
+            (sexps[0].open.clone(), sexps[0].close.clone()),
+            (Some(1..2), Some(7..8))
+        );
+        assert_eq!(
+            sexps.iter().map(|sexp| sexp.parent).collect::<Vec<_>>(),
+            [None, Some(0), Some(0), Some(2)]
+        );
+
+        // an unclosed list runs to the end
+        let sexps = Syntax::sexps("(a b");
+
+        assert_eq!((sexps[0].end, sexps[0].close.clone()), (4, None));
+    }
+
+    #[test]
+    fn matching() {
+        let delimiters = Syntax::delimiters("(a (b)))");
+        let pair = |offset| {
+            Syntax::matching(&delimiters, offset)
+                .map(|(token, partner)| (token.start, partner.map(|token| token.start)))
+        };
+
+        assert_eq!(delimiters.unmatched.len(), 1);
+        assert_eq!(pair(0), Some((0, Some(6))));
+        assert_eq!(pair(3), Some((3, Some(5))));
+        assert_eq!(pair(6), Some((6, Some(0))));
+        assert_eq!(pair(7), Some((7, None)));
+        // behind the cursor
+        assert_eq!(pair(1), Some((0, Some(6))));
+        assert_eq!(pair(8), Some((7, None)));
+        assert_eq!(pair(2), None);
+    }
+
+    #[test]
+    fn atoms() {
+        assert_eq!(Syntax::atom(":key"), Atom::Keyword);
+        assert_eq!(Syntax::atom("12"), Atom::Number);
+        assert_eq!(Syntax::atom("-1.5"), Atom::Number);
+        assert_eq!(Syntax::atom("#xff"), Atom::Number);
+        assert_eq!(Syntax::atom("mu:car"), Atom::Qualified(3));
+        assert_eq!(Syntax::atom("1+"), Atom::Symbol);
+    }
+
+    #[test]
+    fn indent() {
+        let indent = |text: &str| Syntax::indent(text, text.len());
+
+        assert_eq!(indent(""), 0);
+        assert_eq!(indent("(\n"), 1);
+        assert_eq!(indent("(foo\n"), 1);
+        assert_eq!(indent("(foo a\n"), 5);
+        assert_eq!(indent("(defun f (x)\n"), 2);
+        assert_eq!(indent("(core:defun f (x)\n"), 2);
+        assert_eq!(indent("  (let ((a 1))\n"), 4);
+        assert_eq!(indent("(foo \"a\n"), 0);
+        assert_eq!(indent("(foo \"(\" a\n"), 5);
+    }
+
+    #[test]
+    fn reindent() {
+        assert_eq!(
+            Syntax::reindent("(let ((a 1))\na\n    b)", 0..3),
+            "(let ((a 1))\n  a\n  b)"
+        );
+        assert_eq!(
+            Syntax::reindent("(foo a\nb\n   c)", 1..2),
+            "(foo a\n     b\n   c)"
+        );
+        // lines inside a string are the string's
+        assert_eq!(
+            Syntax::reindent("(foo \"a\n  b\"\nc)", 0..3),
+            "(foo \"a\n  b\"\n     c)"
+        );
+    }
+
+    #[test]
+    fn pretty() {
+        assert_eq!(Syntax::pretty("(a b c)", 0, 80), "(a b c)");
+        assert_eq!(
+            Syntax::pretty("(foo alpha beta)", 0, 10),
+            "(foo alpha\n     beta)"
+        );
+        assert_eq!(
+            Syntax::pretty("(defun f (x) (g x) (h x))", 0, 16),
+            "(defun f (x)\n  (g x)\n  (h x))"
+        );
+        assert_eq!(Syntax::pretty("((a b) c d)", 0, 8), "((a b)\n c\n d)");
+        assert_eq!(
+            Syntax::pretty("'(alpha (beta gamma delta))", 2, 20),
+            "'(alpha (beta gamma\n                delta))"
+        );
+
+        // what pretty lays out, indent agrees with
+        let pretty = Syntax::pretty("(let ((a 1) (b 2)) (foo a b) (bar (baz a) b))", 0, 20);
+        let lines = 0..pretty.lines().count();
+
+        assert_eq!(Syntax::reindent(&pretty, lines), pretty);
+    }
+
+    #[test]
+    fn in_literal() {
+        let cases = [
+            ("\"a", 2, true),
+            ("\"a\"", 3, false),
+            ("\"a\" b", 1, true),
+            ("#\\", 2, true),
+            ("#\\a", 3, false),
+            ("; c", 3, true),
+            ("; c\n", 4, false),
+            ("#| c", 4, true),
+            ("#| c |# a", 9, false),
+            ("(a b)", 3, false),
+        ];
+
+        for (text, offset, expected) in cases {
+            assert_eq!(
+                Syntax::in_literal(text, offset),
+                expected,
+                "{text:?} at {offset}"
+            );
+        }
+    }
+}